@@ -0,0 +1,61 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use range_vec::RangeVec;
+
+const DISTANCE: usize = 10_000_000;
+
+fn grow_right(c: &mut Criterion) {
+    c.bench_function("grow right", |b| {
+        b.iter_batched(
+            || {
+                let mut range_vec = RangeVec::<u8>::new();
+                range_vec.set(0, 1);
+                range_vec
+            },
+            |mut range_vec| {
+                range_vec.set(black_box(DISTANCE), 1);
+                range_vec
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn grow_left(c: &mut Criterion) {
+    c.bench_function("grow left", |b| {
+        b.iter_batched(
+            || {
+                let mut range_vec = RangeVec::<u8>::new();
+                range_vec.set(DISTANCE, 1);
+                range_vec
+            },
+            |mut range_vec| {
+                range_vec.set(black_box(0), 1);
+                range_vec
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn grow_both_contiguous(c: &mut Criterion) {
+    c.bench_function("make_contiguous_with grow both", |b| {
+        b.iter_batched(
+            || {
+                let mut range_vec = RangeVec::<u8>::new();
+                range_vec.set(DISTANCE / 2, 1);
+                range_vec
+            },
+            |mut range_vec| {
+                range_vec.make_contiguous_with(black_box(0..DISTANCE), |slice| {
+                    slice[0] = 1;
+                    slice[DISTANCE - 1] = 1;
+                });
+                range_vec
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, grow_right, grow_left, grow_both_contiguous);
+criterion_main!(benches);
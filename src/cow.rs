@@ -0,0 +1,335 @@
+use std::{
+    collections::BTreeMap,
+    marker::PhantomData,
+    ops::{Index, RangeInclusive},
+    sync::Arc,
+};
+
+use crate::{index::add, indices, RangeIndex, RangeVec};
+
+/// `CowRangeVec` is a variant of [`RangeVec`] whose values are stored in fixed-size chunks held in
+/// [`Arc`]s, so that snapshots of it are cheap.
+///
+/// Taking a [`snapshot`](CowRangeVec::snapshot) only clones the pointers to the chunks, and a
+/// chunk is only copied the first time it is written to while it is shared. Untouched regions are
+/// therefore shared between a live `CowRangeVec` and all of its snapshots, which makes it suitable
+/// for keeping a history of frames for rewind.
+///
+/// Chunks are aligned to multiples of `chunk_len` indices from `I::MIN`, and a chunk is dropped
+/// once all of its values are default. Like [`RangeVec`], every index outside of the stored chunks
+/// holds the default value.
+///
+/// # Examples
+///
+/// ```
+/// # use range_vec::CowRangeVec;
+/// let mut memory: CowRangeVec<u8, u32> = CowRangeVec::new(256);
+/// memory.set(0x1000, 1);
+/// memory.set(0x2000, 2);
+///
+/// let snapshot = memory.snapshot();
+/// memory.set(0x2001, 3);
+/// assert_eq!(memory.shared_chunks(&snapshot), 1);
+/// assert_eq!(snapshot[0x2001], 0);
+/// assert_eq!(memory[0x2001], 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CowRangeVec<T, I = usize> {
+    // Keyed by chunk number, counting from `I::MIN`
+    chunks: BTreeMap<usize, Arc<Vec<T>>>,
+    chunk_len: usize,
+    default_item: T,
+    _index: PhantomData<I>,
+}
+
+impl<T, I> Index<I> for CowRangeVec<T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    type Output = T;
+
+    fn index(&self, index: I) -> &Self::Output {
+        self.get(index)
+    }
+}
+
+impl<T, I> CowRangeVec<T, I>
+where
+    I: RangeIndex,
+{
+    /// Creates an empty `CowRangeVec` where every index holds `default`, storing values in chunks
+    /// of `chunk_len` values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_len` is zero.
+    pub fn with_default(default: T, chunk_len: usize) -> Self {
+        assert!(chunk_len > 0, "chunk length of CowRangeVec must be nonzero");
+        Self {
+            chunks: BTreeMap::new(),
+            chunk_len,
+            default_item: default,
+            _index: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the default value, which is returned for any index outside of the
+    /// stored chunks.
+    pub fn default_value(&self) -> &T {
+        &self.default_item
+    }
+
+    /// Returns the number of values in each chunk.
+    pub fn chunk_len(&self) -> usize {
+        self.chunk_len
+    }
+
+    /// Returns the number of stored chunks.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Returns `true` if all values are default.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Take a snapshot of `self`. This is equivalent to [`clone`](Clone::clone), and only clones
+    /// the pointers to the chunks, so it takes time proportional to the number of chunks rather
+    /// than the number of values. Writing to either copy afterwards only copies the chunks that
+    /// are written to.
+    pub fn snapshot(&self) -> Self
+    where
+        T: Clone,
+    {
+        self.clone()
+    }
+
+    /// Returns the number of chunks which are shared between `self` and `other`, such as between a
+    /// `CowRangeVec` and a snapshot of it.
+    pub fn shared_chunks(&self, other: &Self) -> usize {
+        self.chunks
+            .iter()
+            .filter(|(number, chunk)| {
+                other
+                    .chunks
+                    .get(number)
+                    .is_some_and(|other_chunk| Arc::ptr_eq(chunk, other_chunk))
+            })
+            .count()
+    }
+
+    /// Returns the chunk number of `index`, and its position within the chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the distance from `I::MIN` to `index` does not fit in a `usize`.
+    fn locate(&self, index: I) -> (usize, usize) {
+        let distance = index
+            .checked_offset_from(I::MIN)
+            .expect("index is too far from I::MIN to locate its chunk in CowRangeVec");
+        (distance / self.chunk_len, distance % self.chunk_len)
+    }
+
+    /// Returns the index at `position` within chunk `number`.
+    fn index_of(&self, number: usize, position: usize) -> I {
+        add(I::MIN, number * self.chunk_len + position)
+    }
+}
+
+impl<T, I> CowRangeVec<T, I>
+where
+    T: Default,
+    I: RangeIndex,
+{
+    /// Creates an empty (all-default) `CowRangeVec`, storing values in chunks of `chunk_len`
+    /// values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_len` is zero.
+    pub fn new(chunk_len: usize) -> Self {
+        Self::with_default(T::default(), chunk_len)
+    }
+}
+
+impl<T, I> CowRangeVec<T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    /// Provides a reference to the element at the given index, or to a default element.
+    pub fn get(&self, index: I) -> &T {
+        match index.checked_offset_from(I::MIN) {
+            Some(distance) => self
+                .chunks
+                .get(&(distance / self.chunk_len))
+                .map_or(&self.default_item, |chunk| {
+                    &chunk[distance % self.chunk_len]
+                }),
+            None => &self.default_item,
+        }
+    }
+
+    /// Set the value at index `index`. If the chunk containing `index` is shared with a snapshot,
+    /// it is copied first. A new chunk is allocated if a non-default value is written outside of
+    /// the stored chunks, and a chunk is dropped once all of its values are default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the distance from `I::MIN` to `index` does not fit in a `usize`, which is only
+    /// possible for index types wider than `usize`, such as `u128`.
+    pub fn set(&mut self, index: I, value: T) {
+        let (number, position) = self.locate(index);
+        if value == self.default_item {
+            let Some(chunk) = self.chunks.get_mut(&number) else {
+                return;
+            };
+            if chunk[position] == self.default_item {
+                // Don't copy a shared chunk for a write that changes nothing
+                return;
+            }
+            let chunk = Arc::make_mut(chunk);
+            chunk[position] = value;
+            if chunk.iter().all(|item| item == &self.default_item) {
+                self.chunks.remove(&number);
+            }
+        } else {
+            let chunk = self
+                .chunks
+                .entry(number)
+                .or_insert_with(|| Arc::new(vec![self.default_item.clone(); self.chunk_len]));
+            Arc::make_mut(chunk)[position] = value;
+        }
+    }
+
+    /// Mutate the value at index `index`, writing it back as with [`set`](CowRangeVec::set) only
+    /// if it was changed. Any value returned from the passed closure `f` will be returned from the
+    /// method.
+    pub fn get_mut_with<F, R>(&mut self, index: I, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut value = self.get(index).clone();
+        let ret = f(&mut value);
+        if &value != self.get(index) {
+            self.set(index, value);
+        }
+        ret
+    }
+
+    /// Reset the value at a given index to the default value.
+    pub fn reset(&mut self, index: I) {
+        self.set(index, self.default_item.clone());
+    }
+
+    /// Returns the smallest inclusive range containing every non-default value, or `None` if all
+    /// values are default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::CowRangeVec;
+    /// let mut range_vec: CowRangeVec<u8> = CowRangeVec::new(16);
+    /// range_vec.set(5, 1);
+    /// range_vec.set(40, 2);
+    /// assert_eq!(range_vec.range(), Some(5..=40));
+    /// ```
+    pub fn range(&self) -> Option<RangeInclusive<I>> {
+        let (&first_number, first) = self.chunks.first_key_value()?;
+        let (&last_number, last) = self.chunks.last_key_value()?;
+        let is_default = |item: &T| item == &self.default_item;
+        let start = first.iter().position(|item| !is_default(item))?;
+        let end = last.iter().rposition(|item| !is_default(item))?;
+        Some(self.index_of(first_number, start)..=self.index_of(last_number, end))
+    }
+
+    /// Copies the values of `self` into a [`RangeVec`] with the same default value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::{CowRangeVec, RangeVec};
+    /// let mut range_vec: RangeVec<u8> = RangeVec::new();
+    /// range_vec.copy_from_slice(30, &[1, 0, 2]);
+    /// let cow = CowRangeVec::from_range_vec(&range_vec, 16);
+    /// assert_eq!(cow.chunk_count(), 2);
+    /// assert_eq!(cow.to_range_vec(), range_vec);
+    /// ```
+    pub fn to_range_vec(&self) -> RangeVec<T, I> {
+        let mut range_vec = RangeVec::with_default(self.default_item.clone());
+        if let Some(range) = self.range() {
+            let start = *range.start();
+            let values = indices(Some(range)).map(|i| self.get(i).clone());
+            range_vec.set_range(start, values);
+        }
+        range_vec
+    }
+
+    /// Copies the values of `range_vec` into a new `CowRangeVec` with the same default value,
+    /// storing values in chunks of `chunk_len` values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_len` is zero.
+    pub fn from_range_vec(range_vec: &RangeVec<T, I>, chunk_len: usize) -> Self {
+        let mut cow = Self::with_default(range_vec.default_item.clone(), chunk_len);
+        for (index, value) in range_vec.stored() {
+            cow.set(index, value.clone());
+        }
+        cow
+    }
+}
+
+#[test]
+fn test_cow_range_vec() {
+    let mut range_vec = CowRangeVec::<u8, i16>::new(4);
+    range_vec.set(-3, 1);
+    range_vec.set(2, 2);
+    assert_eq!(range_vec.chunk_count(), 2);
+    assert_eq!(range_vec.range(), Some(-3..=2));
+
+    let snapshot = range_vec.snapshot();
+    assert_eq!(range_vec.shared_chunks(&snapshot), 2);
+
+    // Writing a default value where there already is one doesn't copy the chunk
+    range_vec.set(-4, 0);
+    range_vec.set(100, 0);
+    assert_eq!(range_vec.shared_chunks(&snapshot), 2);
+
+    range_vec.get_mut_with(3, |v| *v += 3);
+    assert_eq!(range_vec.shared_chunks(&snapshot), 1);
+    assert_eq!(range_vec.range(), Some(-3..=3));
+    assert_eq!(snapshot.range(), Some(-3..=2));
+
+    range_vec.reset(-3);
+    assert_eq!(range_vec.chunk_count(), 1);
+    assert_eq!(range_vec.range(), Some(2..=3));
+    assert_eq!(snapshot[-3], 1);
+
+    range_vec.set(i16::MIN, 5);
+    assert_eq!(range_vec.range(), Some(i16::MIN..=3));
+    assert_eq!(
+        CowRangeVec::from_range_vec(&range_vec.to_range_vec(), 3).to_range_vec(),
+        range_vec.to_range_vec()
+    );
+
+    // The last chunk may extend past `I::MAX`
+    let mut edge = CowRangeVec::<u8, u8>::new(3);
+    edge.set(u8::MAX, 1);
+    edge.set(u8::MAX - 2, 2);
+    assert_eq!(edge.range(), Some(u8::MAX - 2..=u8::MAX));
+    assert_eq!(edge[u8::MAX], 1);
+    assert_eq!(edge.to_range_vec().range(), Some(u8::MAX - 2..=u8::MAX));
+    edge.reset(u8::MAX - 2);
+    edge.reset(u8::MAX);
+    assert_eq!(edge.chunk_count(), 0);
+}
+
+#[test]
+#[should_panic(expected = "index is too far from I::MIN to locate its chunk in CowRangeVec")]
+fn test_cow_range_vec_wide_index() {
+    let mut range_vec = CowRangeVec::<u8, u128>::new(16);
+    range_vec.set(u128::MAX, 1);
+}
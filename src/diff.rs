@@ -0,0 +1,168 @@
+use std::{iter::FusedIterator, ops::RangeInclusive};
+
+use crate::{index::sub, Iter, RangeIndex, RangeVec};
+
+/// A contiguous span of indices where two [`RangeVec`]s hold different values.
+///
+/// This `struct` is emitted by the [`Diff`] iterator, created by the [`diff`](RangeVec::diff)
+/// method on [`RangeVec`]. See its documentation for more.
+pub struct DiffSpan<'a, T: 'a, I = usize> {
+    /// The indices covered by this span. The values differ at every index in the span.
+    pub range: RangeInclusive<I>,
+    /// An iterator over the old values (from `self`) in this span.
+    pub old: Iter<'a, T, I>,
+    /// An iterator over the new values (from `other`) in this span.
+    pub new: Iter<'a, T, I>,
+}
+
+/// An iterator over the spans where two [`RangeVec`]s differ.
+///
+/// This `struct` is created by the [`diff`](RangeVec::diff) method on [`RangeVec`]. See its
+/// documentation for more.
+pub struct Diff<'a, T: 'a, I = usize> {
+    old: &'a RangeVec<T, I>,
+    new: &'a RangeVec<T, I>,
+    old_range: Option<RangeInclusive<I>>,
+    new_range: Option<RangeInclusive<I>>,
+    // `None` once every index has been compared
+    position: Option<I>,
+}
+
+impl<'a, T, I> Diff<'a, T, I>
+where
+    I: RangeIndex,
+{
+    pub(super) fn new(old: &'a RangeVec<T, I>, new: &'a RangeVec<T, I>) -> Self {
+        Self {
+            old,
+            new,
+            old_range: old.range(),
+            new_range: new.range(),
+            position: Some(I::MIN),
+        }
+    }
+
+    /// If `index` is outside of both stored ranges, returns the last index of the run of indices
+    /// starting at `index` where both values are default.
+    fn default_run_last(&self, index: I) -> Option<I> {
+        let stored = [&self.old_range, &self.new_range];
+        if stored
+            .iter()
+            .any(|range| range.as_ref().is_some_and(|range| range.contains(&index)))
+        {
+            return None;
+        }
+        let last = stored
+            .iter()
+            .filter_map(|range| range.as_ref().map(|range| *range.start()))
+            .filter(|&start| start > index)
+            .min()
+            .map_or(I::MAX, |start| sub(start, 1));
+        Some(last)
+    }
+}
+
+impl<'a, T, I> Diff<'a, T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    /// Returns the end of the run of indices starting at `start` where the values are either all
+    /// equal or all different, depending on `differ`. This is the first index after the run, or
+    /// `None` if the run continues through `I::MAX`.
+    fn run_end(&self, start: I, differ: bool) -> Option<I> {
+        let mut position = start;
+        loop {
+            let last = match self.default_run_last(position) {
+                Some(last) => {
+                    if (self.old.default_item != self.new.default_item) != differ {
+                        return Some(position);
+                    }
+                    last
+                }
+                None => {
+                    if (self.old.get(position) != self.new.get(position)) != differ {
+                        return Some(position);
+                    }
+                    position
+                }
+            };
+            position = last.checked_add_usize(1)?;
+        }
+    }
+}
+
+impl<'a, T, I> Iterator for Diff<'a, T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    type Item = DiffSpan<'a, T, I>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self
+            .position
+            .and_then(|position| self.run_end(position, false));
+        let Some(start) = start else {
+            self.position = None;
+            return None;
+        };
+        self.position = self.run_end(start, true);
+        let range = start..=self.position.map_or(I::MAX, |end| sub(end, 1));
+        Some(DiffSpan {
+            old: self.old.iter(range.clone()),
+            new: self.new.iter(range.clone()),
+            range,
+        })
+    }
+}
+
+impl<'a, T, I> FusedIterator for Diff<'a, T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+}
+
+#[test]
+fn test_diff() {
+    let mut old = RangeVec::<u8>::new();
+    old.copy_from_slice(10, &[1, 2, 3, 4]);
+    let mut new = old.clone();
+    new.set(11, 5);
+    new.set(12, 6);
+    new.set(13, 0);
+    new.set(1_000_000, 7);
+    old.set(2, 8);
+
+    let spans: Vec<_> = old
+        .diff(&new)
+        .map(|span| {
+            (
+                span.range,
+                span.old.copied().collect::<Vec<_>>(),
+                span.new.copied().collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        spans,
+        vec![
+            (2..=2, vec![8], vec![0]),
+            (11..=13, vec![2, 3, 4], vec![5, 6, 0]),
+            (1_000_000..=1_000_000, vec![0], vec![7]),
+        ]
+    );
+
+    assert_eq!(old.diff(&old).count(), 0);
+    assert_eq!(RangeVec::<u8>::new().diff(&RangeVec::new()).count(), 0);
+
+    // Different default values differ everywhere outside of the stored ranges
+    let mut sentinel = RangeVec::<u8, u8>::with_default(0xFF);
+    sentinel.set(10, 0);
+    let ranges: Vec<_> = RangeVec::<u8, u8>::new()
+        .diff(&sentinel)
+        .map(|span| span.range)
+        .collect();
+    assert_eq!(ranges, vec![0..=9, 11..=u8::MAX]);
+}
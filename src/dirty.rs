@@ -0,0 +1,88 @@
+use std::ops::RangeInclusive;
+
+use crate::RangeIndex;
+
+/// A point in the history of a [`RangeVec`](crate::RangeVec), from which modified spans may be
+/// queried.
+///
+/// This `struct` is created by the [`checkpoint`](crate::RangeVec::checkpoint) method on
+/// [`RangeVec`](crate::RangeVec). See its documentation for more.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Checkpoint(u64);
+
+/// A log of the spans written to since the first checkpoint, each tagged with the generation it
+/// was written in. Generations are non-decreasing through the log.
+#[derive(Debug, Clone)]
+pub(crate) struct DirtyLog<I> {
+    generation: u64,
+    spans: Vec<(u64, RangeInclusive<I>)>,
+}
+
+impl<I> DirtyLog<I>
+where
+    I: RangeIndex,
+{
+    pub(crate) fn new() -> Self {
+        Self {
+            generation: 0,
+            spans: Vec::new(),
+        }
+    }
+
+    /// Starts a new generation, returning a checkpoint at its beginning.
+    pub(crate) fn checkpoint(&mut self) -> Checkpoint {
+        self.generation += 1;
+        Checkpoint(self.generation)
+    }
+
+    /// Records a write to `range` in the current generation, merging it into the previous span if
+    /// they overlap or touch.
+    pub(crate) fn mark(&mut self, range: RangeInclusive<I>) {
+        if let Some((generation, last)) = self.spans.last_mut() {
+            if *generation == self.generation && touches(last, &range) && touches(&range, last) {
+                *last = *last.start().min(range.start())..=*last.end().max(range.end());
+                return;
+            }
+        }
+        self.spans.push((self.generation, range));
+    }
+
+    /// Returns the sorted, merged spans written to since `checkpoint`.
+    pub(crate) fn since(&self, checkpoint: Checkpoint) -> Vec<RangeInclusive<I>> {
+        let first = self.first_since(checkpoint);
+        let mut spans: Vec<RangeInclusive<I>> = self.spans[first..]
+            .iter()
+            .map(|(_, range)| range.clone())
+            .collect();
+        spans.sort_unstable_by_key(|range| *range.start());
+
+        let mut merged: Vec<RangeInclusive<I>> = Vec::with_capacity(spans.len());
+        for range in spans {
+            match merged.last_mut() {
+                Some(last) if touches(last, &range) => {
+                    *last = *last.start()..=*last.end().max(range.end());
+                }
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+
+    /// Drops every span written before `checkpoint`.
+    pub(crate) fn discard_before(&mut self, checkpoint: Checkpoint) {
+        let first = self.first_since(checkpoint);
+        self.spans.drain(..first);
+    }
+
+    fn first_since(&self, checkpoint: Checkpoint) -> usize {
+        self.spans
+            .partition_point(|(generation, _)| *generation < checkpoint.0)
+    }
+}
+
+/// Returns `true` if `b` starts no later than right after the end of `a`.
+fn touches<I: RangeIndex>(a: &RangeInclusive<I>, b: &RangeInclusive<I>) -> bool {
+    a.end()
+        .checked_add_usize(1)
+        .is_none_or(|next| *b.start() <= next)
+}
@@ -0,0 +1,46 @@
+use std::{
+    collections::TryReserveError,
+    error::Error,
+    fmt::{self, Display},
+};
+
+/// The error type for the fallible methods of [`RangeVec`](crate::RangeVec).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RangeVecError {
+    /// The stored range would hold more than `usize::MAX` values.
+    CapacityOverflow,
+    /// The stored range would be larger than the maximum span set with
+    /// [`with_max_span`](crate::RangeVec::with_max_span), and the [`OverflowPolicy`] doesn't allow
+    /// making room for it.
+    ///
+    /// [`OverflowPolicy`]: crate::OverflowPolicy
+    SpanOverflow,
+    /// The allocator failed to grow the backing storage.
+    TryReserve(TryReserveError),
+}
+
+impl Display for RangeVecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CapacityOverflow => write!(f, "capacity overflow in RangeVec"),
+            Self::SpanOverflow => write!(f, "maximum span exceeded in RangeVec"),
+            Self::TryReserve(_) => write!(f, "failed to grow the backing storage of RangeVec"),
+        }
+    }
+}
+
+impl Error for RangeVecError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::TryReserve(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<TryReserveError> for RangeVecError {
+    fn from(error: TryReserveError) -> Self {
+        Self::TryReserve(error)
+    }
+}
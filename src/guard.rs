@@ -0,0 +1,129 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::{index::offset_from, OverflowPolicy, RangeIndex, RangeVec};
+
+/// A guard providing mutable access to a single element of a [`RangeVec`].
+///
+/// This `struct` is created by the [`get_mut`](RangeVec::get_mut) method on [`RangeVec`]. See its
+/// documentation for more.
+///
+/// When the guard is dropped, the backing storage of the [`RangeVec`] is grown or shrunk as
+/// appropriate, in the same way as [`get_mut_with`](RangeVec::get_mut_with).
+pub struct RefMut<'a, T, I = usize>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    range_vec: &'a mut RangeVec<T, I>,
+    index: I,
+    // `Some` if `index` was outside of the stored range when the guard was created
+    value: Option<T>,
+}
+
+impl<'a, T, I> RefMut<'a, T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    pub(super) fn new(range_vec: &'a mut RangeVec<T, I>, index: I) -> Self {
+        let in_range = index
+            .checked_offset_from(range_vec.offset)
+            .is_some_and(|index| index < range_vec.data.len());
+        if !in_range && matches!(range_vec.max_span, Some((_, OverflowPolicy::Reject))) {
+            // Check now rather than when the guard is dropped, since panicking in drop while
+            // already unwinding would abort. Rejecting a write doesn't change the stored range.
+            range_vec
+                .make_room(index, index)
+                .unwrap_or_else(|error| panic!("{error}"));
+        }
+        let value = (!in_range).then(|| range_vec.default_item.clone());
+        Self {
+            range_vec,
+            index,
+            value,
+        }
+    }
+}
+
+impl<'a, T, I> Deref for RefMut<'a, T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        match &self.value {
+            Some(value) => value,
+            None => &self.range_vec.data[offset_from(self.index, self.range_vec.offset)],
+        }
+    }
+}
+
+impl<'a, T, I> DerefMut for RefMut<'a, T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match &mut self.value {
+            Some(value) => value,
+            None => &mut self.range_vec.data[offset_from(self.index, self.range_vec.offset)],
+        }
+    }
+}
+
+impl<'a, T, I> Drop for RefMut<'a, T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    fn drop(&mut self) {
+        let written = match self.value.take() {
+            Some(value) => self.range_vec.grow_and_set(self.index, value),
+            None => {
+                self.range_vec.shrink(self.index);
+                true
+            }
+        };
+        if written {
+            self.range_vec.mark_written_index(self.index);
+        }
+    }
+}
+
+#[test]
+fn test_ref_mut() {
+    let mut range_vec = RangeVec::<i32>::new();
+    *range_vec.get_mut(5) += 1;
+    *range_vec.get_mut(7) = 2;
+    assert_eq!(range_vec.range(), Some(5..=7));
+    assert_eq!(*range_vec.get_mut(6), 0);
+    assert_eq!(range_vec.range(), Some(5..=7));
+
+    *range_vec.get_mut(5) -= 1;
+    assert_eq!(range_vec.range(), Some(7..=7));
+
+    {
+        let mut guard = range_vec.get_mut(7);
+        *guard = 0;
+        *guard = 3;
+    }
+    assert_eq!(range_vec[7], 3);
+
+    *range_vec.get_mut(7) = 0;
+    assert!(range_vec.is_empty());
+
+    let mut range_vec = RangeVec::<i32>::with_max_span(4, OverflowPolicy::Reject);
+    *range_vec.get_mut(5) = 1;
+    *range_vec.get_mut(8) = 2;
+    assert_eq!(range_vec.range(), Some(5..=8));
+}
+
+#[test]
+#[should_panic(expected = "maximum span exceeded in RangeVec")]
+fn test_ref_mut_span_overflow() {
+    let mut range_vec = RangeVec::<i32>::with_max_span(4, OverflowPolicy::Reject);
+    range_vec.set(5, 1);
+    let _guard = range_vec.get_mut(9);
+}
@@ -0,0 +1,144 @@
+use std::fmt::Debug;
+
+/// A type that may be used to index into a [`RangeVec`](crate::RangeVec).
+///
+/// This trait is implemented for all primitive integer types, so that a `RangeVec` may be indexed
+/// by signed positions which grow left past zero, or by fixed-width addresses such as `u64`
+/// regardless of the size of `usize` on the target.
+///
+/// Implementations must be consistent with [`Ord`]: for any `a <= b`, `a.checked_add_usize(n)`
+/// must return `Some(b)` exactly when `b.checked_offset_from(a)` returns `Some(n)`.
+pub trait RangeIndex: Copy + Ord + Debug {
+    /// The smallest index. A range unbounded on the left will start at this index (inclusive).
+    const MIN: Self;
+
+    /// The largest index. A range unbounded on the right will end at this index (inclusive).
+    const MAX: Self;
+
+    /// Returns the distance from `origin` to `self`, or `None` if `self` is less than `origin` or
+    /// the distance does not fit in a `usize`.
+    fn checked_offset_from(self, origin: Self) -> Option<usize>;
+
+    /// Returns the index `n` positions to the right of `self`, or `None` on overflow.
+    fn checked_add_usize(self, n: usize) -> Option<Self>;
+
+    /// Returns the index `n` positions to the left of `self`, or `None` on overflow.
+    fn checked_sub_usize(self, n: usize) -> Option<Self>;
+}
+
+macro_rules! impl_range_index_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl RangeIndex for $t {
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                fn checked_offset_from(self, origin: Self) -> Option<usize> {
+                    self.checked_sub(origin)
+                        .and_then(|distance| usize::try_from(distance).ok())
+                }
+
+                fn checked_add_usize(self, n: usize) -> Option<Self> {
+                    <$t>::try_from(n).ok().and_then(|n| self.checked_add(n))
+                }
+
+                fn checked_sub_usize(self, n: usize) -> Option<Self> {
+                    <$t>::try_from(n).ok().and_then(|n| self.checked_sub(n))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_range_index_signed {
+    ($($t:ty => $u:ty),*) => {
+        $(
+            impl RangeIndex for $t {
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                fn checked_offset_from(self, origin: Self) -> Option<usize> {
+                    (self >= origin)
+                        .then(|| self.abs_diff(origin))
+                        .and_then(|distance| usize::try_from(distance).ok())
+                }
+
+                fn checked_add_usize(self, n: usize) -> Option<Self> {
+                    <$u>::try_from(n)
+                        .ok()
+                        .and_then(|n| self.checked_add_unsigned(n))
+                }
+
+                fn checked_sub_usize(self, n: usize) -> Option<Self> {
+                    <$u>::try_from(n)
+                        .ok()
+                        .and_then(|n| self.checked_sub_unsigned(n))
+                }
+            }
+        )*
+    };
+}
+
+impl_range_index_unsigned!(u8, u16, u32, u64, u128, usize);
+impl_range_index_signed!(
+    i8 => u8,
+    i16 => u16,
+    i32 => u32,
+    i64 => u64,
+    i128 => u128,
+    isize => usize
+);
+
+/// Returns the index `n` positions to the right of `index`.
+///
+/// # Panics
+///
+/// Panics if the result overflows `I`.
+pub(crate) fn add<I: RangeIndex>(index: I, n: usize) -> I {
+    index
+        .checked_add_usize(n)
+        .expect("index overflow in RangeVec")
+}
+
+/// Returns the index `n` positions to the left of `index`.
+///
+/// # Panics
+///
+/// Panics if the result overflows `I`.
+pub(crate) fn sub<I: RangeIndex>(index: I, n: usize) -> I {
+    index
+        .checked_sub_usize(n)
+        .expect("index overflow in RangeVec")
+}
+
+/// Returns the distance from `origin` to `index`.
+///
+/// # Panics
+///
+/// Panics if `index` is less than `origin`, or if the distance does not fit in a `usize`.
+pub(crate) fn offset_from<I: RangeIndex>(index: I, origin: I) -> usize {
+    index
+        .checked_offset_from(origin)
+        .expect("index distance out of range in RangeVec")
+}
+
+#[test]
+fn test_range_index() {
+    assert_eq!(5u8.checked_offset_from(3), Some(2));
+    assert_eq!(3u8.checked_offset_from(5), None);
+    assert_eq!(250u8.checked_add_usize(5), Some(255));
+    assert_eq!(250u8.checked_add_usize(6), None);
+    assert_eq!(250u8.checked_add_usize(300), None);
+
+    assert_eq!(100i8.checked_offset_from(-100), Some(200));
+    assert_eq!((-100i8).checked_offset_from(100), None);
+    assert_eq!((-100i8).checked_add_usize(200), Some(100));
+    assert_eq!(100i8.checked_sub_usize(228), Some(-128));
+    assert_eq!(100i8.checked_sub_usize(229), None);
+
+    assert_eq!(
+        i32::MAX.checked_offset_from(i32::MIN),
+        Some(u32::MAX as usize)
+    );
+    assert_eq!(u128::MAX.checked_offset_from(0), None);
+}
@@ -1,69 +1,464 @@
-use std::{collections::vec_deque, iter::FusedIterator, ops::Range};
+use std::{
+    collections::{vec_deque, VecDeque},
+    iter::{Enumerate, FusedIterator},
+    ops::RangeInclusive,
+};
 
-use crate::RangeVec;
+use crate::{
+    index::{add, offset_from, sub},
+    range_intersection, RangeIndex, RangeVec,
+};
 
 /// An iterator over the elements of a [`RangeVec`].
 ///
 /// This `struct` is created by the [`iter`](RangeVec::iter) method on [`RangeVec`]. See its
 /// documentation for more.
-pub struct Iter<'a, T: 'a> {
-    iter_range: Range<usize>,
-    filled_range: Range<usize>,
+pub struct Iter<'a, T: 'a, I = usize> {
+    // `None` once every index has been emitted
+    iter_range: Option<RangeInclusive<I>>,
+    filled_range: Option<RangeInclusive<I>>,
     filled_iter: vec_deque::Iter<'a, T>,
     default_item: &'a T,
 }
 
-impl<'a, T> Iter<'a, T> {
-    pub(super) fn new(range_vec: &'a RangeVec<T>, range: Range<usize>) -> Self {
+impl<'a, T, I> Iter<'a, T, I>
+where
+    I: RangeIndex,
+{
+    pub(super) fn new(range_vec: &'a RangeVec<T, I>, range: Option<RangeInclusive<I>>) -> Self {
+        // Only the part of the stored range that overlaps the iterated range is visited
+        let filled_range = range
+            .as_ref()
+            .zip(range_vec.range())
+            .and_then(|(range, stored)| range_intersection(range, &stored));
+        let filled_iter = match &filled_range {
+            Some(filled_range) => range_vec
+                .data
+                .range(range_vec.saturating_positions(filled_range)),
+            None => range_vec.data.range(0..0),
+        };
         Self {
             iter_range: range,
-            filled_range: range_vec.offset..range_vec.offset + range_vec.data.len(),
-            filled_iter: range_vec.data.iter(),
+            filled_range,
+            filled_iter,
             default_item: &range_vec.default_item,
         }
     }
+
+    fn is_filled(&self, index: I) -> bool {
+        self.filled_range
+            .as_ref()
+            .is_some_and(|filled_range| filled_range.contains(&index))
+    }
 }
 
-impl<'a, T> Iterator for Iter<'a, T> {
+impl<'a, T, I> Iterator for Iter<'a, T, I>
+where
+    I: RangeIndex,
+{
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.iter_range.is_empty() {
-            None
-        } else if self.filled_range.contains(&self.iter_range.start) {
-            self.iter_range.start += 1;
-            self.filled_range.start += 1;
+        let (start, end) = self.iter_range.clone()?.into_inner();
+        self.iter_range = (start < end).then(|| add(start, 1)..=end);
+        if self.is_filled(start) {
             self.filled_iter.next()
         } else {
-            self.iter_range.start += 1;
             Some(self.default_item)
         }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let size = self.iter_range.end - self.iter_range.start;
-        (size, Some(size))
+        let Some(iter_range) = &self.iter_range else {
+            return (0, Some(0));
+        };
+        match iter_range
+            .end()
+            .checked_offset_from(*iter_range.start())
+            .and_then(|distance| distance.checked_add(1))
+        {
+            Some(size) => (size, Some(size)),
+            None => (usize::MAX, None),
+        }
     }
 }
 
-impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+impl<'a, T, I> DoubleEndedIterator for Iter<'a, T, I>
+where
+    I: RangeIndex,
+{
     fn next_back(&mut self) -> Option<Self::Item> {
-        if self.iter_range.is_empty() {
-            None
-        } else if self.filled_range.contains(&(self.iter_range.end - 1)) {
-            self.iter_range.end -= 1;
-            self.filled_range.end -= 1;
+        let (start, end) = self.iter_range.clone()?.into_inner();
+        self.iter_range = (start < end).then(|| start..=sub(end, 1));
+        if self.is_filled(end) {
             self.filled_iter.next_back()
         } else {
-            self.iter_range.end -= 1;
             Some(self.default_item)
         }
     }
 }
 
-impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+impl<'a, T, I> FusedIterator for Iter<'a, T, I> where I: RangeIndex {}
+
+/// An iterator over the maximal runs of equal elements of a [`RangeVec`].
+///
+/// This `struct` is created by the [`runs`](RangeVec::runs) method on [`RangeVec`]. See its
+/// documentation for more.
+pub struct Runs<'a, T: 'a, I = usize> {
+    // `None` once every index has been emitted
+    iter_range: Option<RangeInclusive<I>>,
+    filled_range: Option<RangeInclusive<I>>,
+    data: &'a VecDeque<T>,
+    offset: I,
+    default_item: &'a T,
+}
+
+impl<'a, T, I> Runs<'a, T, I>
+where
+    I: RangeIndex,
+{
+    pub(super) fn new(range_vec: &'a RangeVec<T, I>, range: Option<RangeInclusive<I>>) -> Self {
+        let filled_range = range
+            .as_ref()
+            .zip(range_vec.range())
+            .and_then(|(range, stored)| range_intersection(range, &stored));
+        Self {
+            iter_range: range,
+            filled_range,
+            data: &range_vec.data,
+            offset: range_vec.offset,
+            default_item: &range_vec.default_item,
+        }
+    }
+}
+
+impl<'a, T, I> Iterator for Runs<'a, T, I>
+where
+    T: Eq,
+    I: RangeIndex,
+{
+    type Item = (RangeInclusive<I>, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, end) = self.iter_range.clone()?.into_inner();
+        let (last, item) = match &self.filled_range {
+            Some(filled_range) if filled_range.contains(&start) => {
+                let first = offset_from(start, self.offset);
+                let limit = offset_from(*filled_range.end(), self.offset);
+                let item = &self.data[first];
+                let len = self
+                    .data
+                    .range(first..=limit)
+                    .position(|other| other != item)
+                    .unwrap_or(limit - first + 1);
+                (add(start, len - 1), item)
+            }
+            Some(filled_range) if start < *filled_range.start() => {
+                (sub(*filled_range.start(), 1), self.default_item)
+            }
+            _ => (end, self.default_item),
+        };
+
+        let last = last.min(end);
+        self.iter_range = (last < end).then(|| add(last, 1)..=end);
+        Some((start..=last, item))
+    }
+}
+
+impl<'a, T, I> DoubleEndedIterator for Runs<'a, T, I>
+where
+    T: Eq,
+    I: RangeIndex,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (start, end) = self.iter_range.clone()?.into_inner();
+        let (first, item) = match &self.filled_range {
+            Some(filled_range) if filled_range.contains(&end) => {
+                let limit = offset_from(*filled_range.start(), self.offset);
+                let last = offset_from(end, self.offset);
+                let item = &self.data[last];
+                let len = self
+                    .data
+                    .range(limit..=last)
+                    .rev()
+                    .position(|other| other != item)
+                    .unwrap_or(last - limit + 1);
+                (sub(end, len - 1), item)
+            }
+            Some(filled_range) if end > *filled_range.end() => {
+                (add(*filled_range.end(), 1), self.default_item)
+            }
+            _ => (start, self.default_item),
+        };
+
+        let first = first.max(start);
+        self.iter_range = (start < first).then(|| start..=sub(first, 1));
+        Some((first..=end, item))
+    }
+}
+
+impl<'a, T, I> FusedIterator for Runs<'a, T, I>
+where
+    T: Eq,
+    I: RangeIndex,
+{
+}
+
+/// An iterator over the elements of the stored range of a [`RangeVec`] and their indices.
+///
+/// This `struct` is created by the [`stored`](RangeVec::stored) method on [`RangeVec`], or by
+/// iterating over `&RangeVec`. See its documentation for more.
+pub struct Stored<'a, T: 'a, I = usize> {
+    start: I,
+    filled_iter: Enumerate<vec_deque::Iter<'a, T>>,
+}
+
+impl<'a, T, I> Stored<'a, T, I>
+where
+    I: RangeIndex,
+{
+    pub(super) fn new(range_vec: &'a RangeVec<T, I>) -> Self {
+        Self {
+            start: range_vec.offset,
+            filled_iter: range_vec.data.iter().enumerate(),
+        }
+    }
+}
+
+impl<'a, T, I> Iterator for Stored<'a, T, I>
+where
+    I: RangeIndex,
+{
+    type Item = (I, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.filled_iter
+            .next()
+            .map(|(i, item)| (add(self.start, i), item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.filled_iter.size_hint()
+    }
+}
+
+impl<'a, T, I> DoubleEndedIterator for Stored<'a, T, I>
+where
+    I: RangeIndex,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.filled_iter
+            .next_back()
+            .map(|(i, item)| (add(self.start, i), item))
+    }
+}
+
+impl<'a, T, I> ExactSizeIterator for Stored<'a, T, I> where I: RangeIndex {}
+
+impl<'a, T, I> FusedIterator for Stored<'a, T, I> where I: RangeIndex {}
+
+/// An owning iterator over the elements of the stored range of a [`RangeVec`] and their indices.
+///
+/// This `struct` is created by the [`into_iter`](IntoIterator::into_iter) method on [`RangeVec`]
+/// (provided by the [`IntoIterator`] trait). See its documentation for more.
+pub struct IntoIter<T, I = usize> {
+    start: I,
+    filled_iter: Enumerate<vec_deque::IntoIter<T>>,
+}
+
+impl<T, I> IntoIter<T, I>
+where
+    I: RangeIndex,
+{
+    pub(super) fn new(range_vec: RangeVec<T, I>) -> Self {
+        Self {
+            start: range_vec.offset,
+            filled_iter: range_vec.data.into_iter().enumerate(),
+        }
+    }
+}
+
+impl<T, I> Iterator for IntoIter<T, I>
+where
+    I: RangeIndex,
+{
+    type Item = (I, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.filled_iter
+            .next()
+            .map(|(i, item)| (add(self.start, i), item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.filled_iter.size_hint()
+    }
+}
+
+impl<T, I> DoubleEndedIterator for IntoIter<T, I>
+where
+    I: RangeIndex,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.filled_iter
+            .next_back()
+            .map(|(i, item)| (add(self.start, i), item))
+    }
+}
+
+impl<T, I> ExactSizeIterator for IntoIter<T, I> where I: RangeIndex {}
+
+impl<T, I> FusedIterator for IntoIter<T, I> where I: RangeIndex {}
+
+/// An iterator over the non-default elements of a [`RangeVec`] and their indices.
+///
+/// This `struct` is created by the [`non_default`](RangeVec::non_default) and
+/// [`iter_non_default`](RangeVec::iter_non_default) methods on [`RangeVec`]. See their
+/// documentation for more.
+pub struct NonDefault<'a, T: 'a, I = usize> {
+    start: I,
+    filled_iter: Enumerate<vec_deque::Iter<'a, T>>,
+    default_item: &'a T,
+}
+
+impl<'a, T, I> NonDefault<'a, T, I>
+where
+    I: RangeIndex,
+{
+    pub(super) fn new(range_vec: &'a RangeVec<T, I>, range: Option<RangeInclusive<I>>) -> Self {
+        // Only the part of the stored range that overlaps the iterated range is visited
+        let filled_range = range
+            .zip(range_vec.range())
+            .and_then(|(range, stored)| range_intersection(&range, &stored));
+        let (start, filled_iter) = match filled_range {
+            Some(filled_range) => (
+                *filled_range.start(),
+                range_vec
+                    .data
+                    .range(range_vec.saturating_positions(&filled_range)),
+            ),
+            None => (range_vec.offset, range_vec.data.range(0..0)),
+        };
+        Self {
+            start,
+            filled_iter: filled_iter.enumerate(),
+            default_item: &range_vec.default_item,
+        }
+    }
+}
+
+impl<'a, T, I> Iterator for NonDefault<'a, T, I>
+where
+    T: Eq,
+    I: RangeIndex,
+{
+    type Item = (I, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.filled_iter
+            .find(|(_, item)| *item != self.default_item)
+            .map(|(i, item)| (add(self.start, i), item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.filled_iter.size_hint().1)
+    }
+}
+
+impl<'a, T, I> DoubleEndedIterator for NonDefault<'a, T, I>
+where
+    T: Eq,
+    I: RangeIndex,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.filled_iter
+            .rfind(|(_, item)| *item != self.default_item)
+            .map(|(i, item)| (add(self.start, i), item))
+    }
+}
+
+impl<'a, T, I> FusedIterator for NonDefault<'a, T, I>
+where
+    T: Eq,
+    I: RangeIndex,
+{
+}
 
-impl<'a, T> FusedIterator for Iter<'a, T> {}
+/// A guard providing mutable iteration over a range of elements of a [`RangeVec`].
+///
+/// This `struct` is created by the [`iter_mut`](RangeVec::iter_mut) method on [`RangeVec`]. See
+/// its documentation for more.
+///
+/// The guard is not an [`Iterator`] itself. The elements are iterated by calling
+/// [`iter`](IterMutGuard::iter), or by iterating over `&mut IterMutGuard` directly, such as with
+/// `for v in &mut range_vec.iter_mut(..)`. The references emitted borrow from the guard, so that
+/// the guard may not be dropped while they are still alive.
+///
+/// The backing storage is grown to include the entire range when the guard is created, and shrunk
+/// once when the guard is dropped or [`finish`](IterMutGuard::finish) is called.
+pub struct IterMutGuard<'a, T, I = usize>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    range_vec: &'a mut RangeVec<T, I>,
+    iter_range: Option<RangeInclusive<I>>,
+}
+
+impl<'a, T, I> IterMutGuard<'a, T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    pub(super) fn new(range_vec: &'a mut RangeVec<T, I>, range: Option<RangeInclusive<I>>) -> Self {
+        if let Some(range) = &range {
+            range_vec.grow_to_include_range(*range.start(), *range.end());
+            range_vec.mark_written(range.clone());
+        }
+        Self {
+            range_vec,
+            iter_range: range,
+        }
+    }
+
+    /// Creates an iterator over the range this guard was created for. The iterator will emit
+    /// values of type `&mut T`.
+    pub fn iter(&mut self) -> vec_deque::IterMut<'_, T> {
+        let positions = match &self.iter_range {
+            Some(iter_range) => self.range_vec.saturating_positions(iter_range),
+            None => 0..0,
+        };
+        self.range_vec.data.range_mut(positions)
+    }
+
+    /// Drops the guard, shrinking the backing storage of the [`RangeVec`] as appropriate. This is
+    /// equivalent to `drop(iter_mut)`.
+    pub fn finish(self) {}
+}
+
+impl<'b, 'a, T, I> IntoIterator for &'b mut IterMutGuard<'a, T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    type Item = &'b mut T;
+    type IntoIter = vec_deque::IterMut<'b, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, I> Drop for IterMutGuard<'a, T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    fn drop(&mut self) {
+        self.range_vec.shrink_left();
+        self.range_vec.shrink_right();
+    }
+}
 
 #[test]
 fn test_iter() {
@@ -73,14 +468,126 @@ fn test_iter() {
     range_vec.set(7, 3);
 
     let mut iter = range_vec.iter(3..9);
-    assert_eq!(iter.len(), 6);
+    assert_eq!(iter.size_hint(), (6, Some(6)));
     assert_eq!(iter.next(), Some(&0));
     assert_eq!(iter.next(), Some(&0));
     assert_eq!(iter.next(), Some(&1));
     assert_eq!(iter.next_back(), Some(&0));
     assert_eq!(iter.next_back(), Some(&3));
-    assert_eq!(iter.len(), 1);
+    assert_eq!(iter.size_hint(), (1, Some(1)));
+    assert_eq!(iter.next(), Some(&2));
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next(), None);
+
+    let mut iter = range_vec.iter(6..7);
     assert_eq!(iter.next(), Some(&2));
     assert_eq!(iter.next(), None);
+
+    // The length of a range spanning every index may not fit in a `usize`
+    assert_eq!(
+        RangeVec::<u8, i128>::new().iter(..).size_hint(),
+        (usize::MAX, None)
+    );
+}
+
+#[test]
+fn test_non_default() {
+    let mut range_vec = RangeVec::<u8>::new();
+    range_vec.set(5, 1);
+    range_vec.set(7, 2);
+    range_vec.set(8, 3);
+
+    let mut iter = range_vec.non_default();
+    assert_eq!(iter.next(), Some((5, &1)));
+    assert_eq!(iter.next_back(), Some((8, &3)));
+    assert_eq!(iter.next(), Some((7, &2)));
+    assert_eq!(iter.next_back(), None);
+    assert_eq!(iter.next(), None);
+
+    let mut iter = range_vec.iter_non_default(6..8);
+    assert_eq!(iter.next(), Some((7, &2)));
     assert_eq!(iter.next(), None);
+    assert_eq!(range_vec.iter_non_default(9..).next(), None);
+    assert_eq!(range_vec.iter_non_default(..5).next(), None);
+}
+
+#[test]
+fn test_runs() {
+    let mut range_vec = RangeVec::<u8>::new();
+    range_vec.copy_from_slice(10, &[1, 1, 0, 0, 2, 1]);
+
+    let runs: Vec<_> = range_vec.runs(..).collect();
+    assert_eq!(
+        runs,
+        vec![
+            (0..=9, &0),
+            (10..=11, &1),
+            (12..=13, &0),
+            (14..=14, &2),
+            (15..=15, &1),
+            (16..=usize::MAX, &0),
+        ]
+    );
+    let mut reversed: Vec<_> = range_vec.runs(..).rev().collect();
+    reversed.reverse();
+    assert_eq!(reversed, runs);
+
+    let mut iter = range_vec.runs(11..20);
+    assert_eq!(iter.next(), Some((11..=11, &1)));
+    assert_eq!(iter.next_back(), Some((16..=19, &0)));
+    assert_eq!(iter.next_back(), Some((15..=15, &1)));
+    assert_eq!(iter.next(), Some((12..=13, &0)));
+    assert_eq!(iter.next_back(), Some((14..=14, &2)));
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next_back(), None);
+
+    assert_eq!(range_vec.runs(3..7).collect::<Vec<_>>(), vec![(3..=6, &0)]);
+    assert_eq!(
+        RangeVec::<u8>::new().runs(3..7).collect::<Vec<_>>(),
+        vec![(3..=6, &0)]
+    );
+}
+
+#[test]
+fn test_into_iter() {
+    let mut range_vec = RangeVec::<u8>::new();
+    range_vec.set(5, 1);
+    range_vec.set(7, 2);
+
+    let mut iter = (&range_vec).into_iter();
+    assert_eq!(iter.len(), 3);
+    assert_eq!(iter.next(), Some((5, &1)));
+    assert_eq!(iter.next_back(), Some((7, &2)));
+    assert_eq!(iter.next(), Some((6, &0)));
+    assert_eq!(iter.next(), None);
+
+    let items: Vec<_> = range_vec.into_iter().rev().collect();
+    assert_eq!(items, vec![(7, 2), (6, 0), (5, 1)]);
+}
+
+#[test]
+fn test_iter_mut() {
+    let mut range_vec = RangeVec::<i32>::new();
+    range_vec.set(5, -1);
+    range_vec.set(6, 2);
+
+    let mut iter_mut = range_vec.iter_mut(3..8);
+    assert_eq!(iter_mut.iter().len(), 5);
+    for (i, item) in iter_mut.iter().enumerate() {
+        *item += i as i32;
+    }
+    iter_mut.finish();
+    assert_eq!(range_vec.range(), Some(4..=7));
+    assert_eq!(
+        range_vec.iter(3..8).copied().collect::<Vec<_>>(),
+        vec![0, 1, 1, 5, 4]
+    );
+
+    for item in &mut range_vec.iter_mut(..10) {
+        *item = 0;
+    }
+    assert!(range_vec.is_empty());
+
+    range_vec.iter_mut(5..5).iter().for_each(|item| *item = 1);
+    assert!(range_vec.is_empty());
 }
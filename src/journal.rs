@@ -0,0 +1,497 @@
+use std::ops::{Index, RangeBounds, RangeInclusive};
+
+use crate::{
+    indices, range_bounds_to_range, range_intersection, OverflowPolicy, RangeIndex, RangeVec,
+};
+
+/// `JournaledRangeVec` is a [`RangeVec`] which records every mutation in a journal, so that it
+/// may be undone and redone.
+///
+/// Each call to a mutating method records the old value of every index it changed as a single
+/// group, which is undone or redone as a whole. Several calls may be combined into one group with
+/// [`group`](JournaledRangeVec::group). Undoing and redoing write through the same methods as any
+/// other mutation, so the stored range stays normalized. Making a new change after undoing
+/// discards the redo history.
+///
+/// Only indices whose value actually changed are recorded, so the journal grows with the number
+/// of changed values rather than with the size of the mutated ranges. Under a maximum span with
+/// [`OverflowPolicy::EvictFar`], values evicted to make room for a write are recorded as well,
+/// which copies the stored values before every write that reaches outside of the stored range.
+///
+/// # Examples
+///
+/// ```
+/// # use range_vec::JournaledRangeVec;
+/// let mut memory: JournaledRangeVec<u8> = JournaledRangeVec::new();
+/// memory.set(0x100, 1);
+/// memory.group(|memory| {
+///     memory.set(0x101, 2);
+///     memory.mutate_many(0x100..0x102, |_, v| *v += 1);
+/// });
+/// assert_eq!((memory[0x100], memory[0x101]), (2, 3));
+///
+/// assert!(memory.undo());
+/// assert_eq!((memory[0x100], memory[0x101]), (1, 0));
+/// assert_eq!(memory.range_vec().range(), Some(0x100..=0x100));
+///
+/// assert!(memory.redo());
+/// assert_eq!((memory[0x100], memory[0x101]), (2, 3));
+/// ```
+#[derive(Debug, Clone)]
+pub struct JournaledRangeVec<T, I = usize> {
+    range_vec: RangeVec<T, I>,
+    undo_stack: Vec<Vec<(I, T)>>,
+    redo_stack: Vec<Vec<(I, T)>>,
+    // `Some` while inside of a call to `group`
+    open_group: Option<Vec<(I, T)>>,
+}
+
+impl<T, I> Index<I> for JournaledRangeVec<T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    type Output = T;
+
+    fn index(&self, index: I) -> &Self::Output {
+        self.range_vec.get(index)
+    }
+}
+
+impl<T, I> From<RangeVec<T, I>> for JournaledRangeVec<T, I> {
+    /// Wraps `range_vec` with an empty journal.
+    fn from(range_vec: RangeVec<T, I>) -> Self {
+        Self {
+            range_vec,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            open_group: None,
+        }
+    }
+}
+
+impl<T, I> JournaledRangeVec<T, I>
+where
+    I: RangeIndex,
+{
+    /// Creates an empty `JournaledRangeVec` where every index holds `default`.
+    pub fn with_default(default: T) -> Self {
+        RangeVec::with_default(default).into()
+    }
+
+    /// Returns a reference to the underlying [`RangeVec`].
+    pub fn range_vec(&self) -> &RangeVec<T, I> {
+        &self.range_vec
+    }
+
+    /// Consumes the `JournaledRangeVec`, returning the underlying [`RangeVec`] and discarding the
+    /// journal.
+    pub fn into_inner(self) -> RangeVec<T, I> {
+        self.range_vec
+    }
+
+    /// Returns `true` if there is a group of changes to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// Returns `true` if there is a group of undone changes to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Discard the whole journal, keeping the current values.
+    pub fn clear_history(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+}
+
+impl<T, I> JournaledRangeVec<T, I>
+where
+    T: Default,
+    I: RangeIndex,
+{
+    /// Creates an empty (all-default) `JournaledRangeVec`.
+    pub fn new() -> Self {
+        RangeVec::new().into()
+    }
+}
+
+impl<T, I> Default for JournaledRangeVec<T, I>
+where
+    T: Default,
+    I: RangeIndex,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, I> JournaledRangeVec<T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    /// Records a group of `(index, old_value)` entries, either into the open group or as a new
+    /// group of its own.
+    fn record(&mut self, entries: Vec<(I, T)>) {
+        if entries.is_empty() {
+            return;
+        }
+        self.redo_stack.clear();
+        match &mut self.open_group {
+            Some(group) => group.extend(entries),
+            None => self.undo_stack.push(entries),
+        }
+    }
+
+    /// Runs `f` on the underlying `RangeVec`, which writes to indices within `written`, and
+    /// records the `(index, old_value)` entries it returns, which must be sorted by index, along
+    /// with any value evicted to make room under the maximum span.
+    fn track<F, R>(&mut self, written: Option<RangeInclusive<I>>, f: F) -> R
+    where
+        F: FnOnce(&mut RangeVec<T, I>) -> (R, Vec<(I, T)>),
+    {
+        // Values are only evicted under EvictFar, by a write outside of the stored range
+        let may_evict = matches!(self.range_vec.max_span, Some((_, OverflowPolicy::EvictFar)))
+            && written.is_some_and(|written| {
+                !self.range_vec.range().is_some_and(|stored| {
+                    stored.contains(written.start()) && stored.contains(written.end())
+                })
+            });
+        let stored: Vec<(I, T)> = if may_evict {
+            self.range_vec
+                .non_default()
+                .map(|(i, v)| (i, v.clone()))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let (ret, entries) = f(&mut self.range_vec);
+        // The copied values come first, so that they are restored last on undo. An index may have
+        // been evicted before it was written, in which case its entry holds the default value.
+        let mut group: Vec<_> = stored
+            .into_iter()
+            .filter(|(i, old)| {
+                self.range_vec.get(*i) != old
+                    || entries.binary_search_by_key(i, |(i, _)| *i).is_ok()
+            })
+            .collect();
+        group.extend(entries);
+        self.record(group);
+        ret
+    }
+
+    /// Copies the values in the part of `range` which is inside of the stored range, returning
+    /// that part along with the values. Values outside of it are default.
+    fn copy_stored(
+        &self,
+        range: &Option<RangeInclusive<I>>,
+    ) -> (Option<RangeInclusive<I>>, Vec<T>) {
+        let stored = range
+            .as_ref()
+            .zip(self.range_vec.range())
+            .and_then(|(range, stored)| range_intersection(range, &stored));
+        let old = match &stored {
+            Some(stored) => self.range_vec.iter(stored.clone()).cloned().collect(),
+            None => Vec::new(),
+        };
+        (stored, old)
+    }
+
+    /// Provides a reference to the element at the given index, or to a default element.
+    pub fn get(&self, index: I) -> &T {
+        self.range_vec.get(index)
+    }
+
+    /// Set the value at index `index`, as with [`RangeVec::set`].
+    pub fn set(&mut self, index: I, value: T) {
+        let old = self.range_vec.get(index).clone();
+        if old != value {
+            self.track(Some(index..=index), |range_vec| {
+                range_vec.set(index, value);
+                let changed = range_vec.get(index) != &old;
+                ((), changed.then_some((index, old)).into_iter().collect())
+            });
+        }
+    }
+
+    /// Mutate the value at index `index`, as with [`RangeVec::get_mut_with`].
+    pub fn get_mut_with<F, R>(&mut self, index: I, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let old = self.range_vec.get(index).clone();
+        self.track(Some(index..=index), |range_vec| {
+            let ret = range_vec.get_mut_with(index, f);
+            let changed = range_vec.get(index) != &old;
+            (ret, changed.then_some((index, old)).into_iter().collect())
+        })
+    }
+
+    /// Mutate a range of values, as with [`RangeVec::mutate_many`].
+    pub fn mutate_many<F>(&mut self, range: impl RangeBounds<I>, mut f: F)
+    where
+        F: FnMut(I, &mut T),
+    {
+        let bounds = (range.start_bound().cloned(), range.end_bound().cloned());
+        self.track(range_bounds_to_range(bounds), |range_vec| {
+            let mut entries = Vec::new();
+            range_vec.mutate_many(bounds, |i, v| {
+                let old = v.clone();
+                f(i, v);
+                if *v != old {
+                    entries.push((i, old));
+                }
+            });
+            ((), entries)
+        })
+    }
+
+    /// Reset the value at a given index to the default value, as with [`RangeVec::reset`].
+    pub fn reset(&mut self, index: I) {
+        let old = self.range_vec.get(index).clone();
+        if &old != self.range_vec.default_value() {
+            self.range_vec.reset(index);
+            self.record(vec![(index, old)]);
+        }
+    }
+
+    /// Reset all values outside of `range` to the default value, as with [`RangeVec::truncate`].
+    pub fn truncate(&mut self, range: impl RangeBounds<I>) {
+        let range = range_bounds_to_range(range);
+        let entries = self
+            .range_vec
+            .non_default()
+            .filter(|(i, _)| !range.as_ref().is_some_and(|range| range.contains(i)))
+            .map(|(i, v)| (i, v.clone()))
+            .collect();
+        self.range_vec.truncate_to(range);
+        self.record(entries);
+    }
+
+    /// Mutably access the backing storage for `range`, as with [`RangeVec::as_mut_slices_with`].
+    /// The old values of the part of the range inside of the stored range are copied before
+    /// calling `f`, so that the changed ones can be recorded.
+    pub fn as_mut_slices_with<F, R>(&mut self, range: impl RangeBounds<I>, f: F) -> R
+    where
+        F: FnOnce(&mut [T], &mut [T]) -> R,
+    {
+        let bounds = (range.start_bound().cloned(), range.end_bound().cloned());
+        let range = range_bounds_to_range(bounds);
+        let (stored, old) = self.copy_stored(&range);
+        self.track(range.clone(), |range_vec| {
+            let ret = range_vec.as_mut_slices_with(bounds, f);
+            (ret, changes(range_vec, range, stored, old))
+        })
+    }
+
+    /// Mutably access the backing storage for `range` as a single slice, as with
+    /// [`RangeVec::make_contiguous_with`]. The old values of the part of the range inside of the
+    /// stored range are copied before calling `f`, so that the changed ones can be recorded.
+    pub fn make_contiguous_with<F, R>(&mut self, range: impl RangeBounds<I>, f: F) -> R
+    where
+        F: FnOnce(&mut [T]) -> R,
+    {
+        let bounds = (range.start_bound().cloned(), range.end_bound().cloned());
+        let range = range_bounds_to_range(bounds);
+        let (stored, old) = self.copy_stored(&range);
+        self.track(range.clone(), |range_vec| {
+            let ret = range_vec.make_contiguous_with(bounds, f);
+            (ret, changes(range_vec, range, stored, old))
+        })
+    }
+
+    /// Combine every change made by `f` into a single group, which is undone or redone as a
+    /// whole. Groups may be nested, in which case the changes of the inner group become part of
+    /// the outer one. Any value returned from `f` will be returned from the method.
+    pub fn group<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        let outer = self.open_group.replace(Vec::new());
+        let ret = f(self);
+        let entries = self.open_group.take().unwrap_or_default();
+        self.open_group = outer;
+        self.record(entries);
+        ret
+    }
+
+    /// Undo the most recent group of changes. Returns `false` if there was nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.undo_stack.pop() {
+            Some(group) => {
+                let inverse = self.apply(group);
+                self.redo_stack.push(inverse);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redo the most recently undone group of changes. Returns `false` if there was nothing to
+    /// redo.
+    pub fn redo(&mut self) -> bool {
+        match self.redo_stack.pop() {
+            Some(group) => {
+                let inverse = self.apply(group);
+                self.undo_stack.push(inverse);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Restores the values recorded in `group` in reverse order, returning the group that would
+    /// restore the values they replaced.
+    fn apply(&mut self, group: Vec<(I, T)>) -> Vec<(I, T)> {
+        // The values are restored to a state which fit in the maximum span, so it is lifted
+        // meanwhile rather than evicting or rejecting anything on the way
+        let max_span = self.range_vec.max_span.take();
+        let inverse = group
+            .into_iter()
+            .rev()
+            .map(|(index, value)| {
+                let old = self.range_vec.get(index).clone();
+                self.range_vec.set(index, value);
+                (index, old)
+            })
+            .collect();
+        self.range_vec.max_span = max_span;
+        inverse
+    }
+}
+
+/// Returns the `(index, old_value)` entries, sorted by index, for every index in `range` whose
+/// value in `range_vec` changed. `old` holds the old values of `stored`, and every other value in
+/// `range` was default.
+fn changes<T, I>(
+    range_vec: &RangeVec<T, I>,
+    range: Option<RangeInclusive<I>>,
+    stored: Option<RangeInclusive<I>>,
+    old: Vec<T>,
+) -> Vec<(I, T)>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    // Values which were default can only have changed within the new stored range
+    let grown = range
+        .zip(range_vec.range())
+        .and_then(|(range, new_stored)| range_intersection(&range, &new_stored));
+    let was_stored = |i: &I| stored.as_ref().is_some_and(|stored| stored.contains(i));
+    let mut entries: Vec<_> = indices(grown)
+        .filter(|i| !was_stored(i) && range_vec.get(*i) != range_vec.default_value())
+        .map(|i| (i, range_vec.default_value().clone()))
+        .collect();
+    entries.extend(
+        indices(stored.clone())
+            .zip(old)
+            .filter(|(i, old)| range_vec.get(*i) != old),
+    );
+    entries.sort_unstable_by_key(|(i, _)| *i);
+    entries
+}
+
+#[test]
+fn test_journaled_range_vec() {
+    let mut range_vec = JournaledRangeVec::<u8>::new();
+    range_vec.set(5, 1);
+    range_vec.set(5, 1);
+    range_vec.get_mut_with(6, |v| *v = 2);
+    range_vec.get_mut_with(7, |v| *v);
+    range_vec.make_contiguous_with(3..8, |slice| slice[0] = 3);
+    range_vec.as_mut_slices_with(4..7, |left, right| {
+        left.iter_mut().chain(right).for_each(|v| *v += 1);
+    });
+    assert_eq!(
+        range_vec
+            .range_vec()
+            .iter(3..7)
+            .copied()
+            .collect::<Vec<_>>(),
+        vec![3, 1, 2, 3]
+    );
+
+    range_vec.truncate(4..);
+    range_vec.reset(6);
+    range_vec.reset(6);
+    assert_eq!(range_vec.range_vec().range(), Some(4..=5));
+
+    // Writes which change nothing are not recorded
+    let mut undone = 0;
+    while range_vec.undo() {
+        undone += 1;
+    }
+    assert_eq!(undone, 6);
+    assert!(range_vec.range_vec().is_empty());
+    assert!(!range_vec.can_undo());
+
+    while range_vec.redo() {}
+    assert_eq!(range_vec.range_vec().range(), Some(4..=5));
+    assert_eq!(range_vec[5], 2);
+
+    range_vec.undo();
+    assert_eq!(range_vec[6], 3);
+    range_vec.set(10, 1);
+    assert!(!range_vec.can_redo());
+
+    // Nested groups are merged into the outermost one
+    let mut range_vec = JournaledRangeVec::<u8>::new();
+    range_vec.group(|range_vec| {
+        range_vec.set(1, 1);
+        range_vec.group(|range_vec| range_vec.set(2, 2));
+        range_vec.set(1, 3);
+    });
+    assert!(range_vec.undo());
+    assert!(range_vec.range_vec().is_empty());
+    assert!(!range_vec.can_undo());
+}
+
+#[test]
+fn test_journaled_range_vec_evict_far() {
+    let mut range_vec: JournaledRangeVec<u8> =
+        RangeVec::with_max_span(4, OverflowPolicy::EvictFar).into();
+    range_vec.set(0, 1);
+    range_vec.set(10, 2);
+    assert_eq!(range_vec.range_vec().range(), Some(10..=10));
+    assert!(range_vec.undo());
+    assert_eq!(range_vec[0], 1);
+    assert_eq!(range_vec[10], 0);
+    assert!(range_vec.redo());
+    assert_eq!(range_vec.range_vec().range(), Some(10..=10));
+
+    // Values evicted by writing a range, including ones it mutated, are restored together
+    range_vec.set(11, 3);
+    range_vec.mutate_many(12..=15, |_, v| *v += 4);
+    range_vec.make_contiguous_with(6..=9, |slice| slice.fill(5));
+    assert_eq!(range_vec.range_vec().range(), Some(6..=9));
+    assert!(range_vec.undo());
+    assert_eq!(range_vec.range_vec().range(), Some(12..=15));
+    assert!(range_vec.undo());
+    assert_eq!(range_vec.range_vec().range(), Some(10..=11));
+    assert_eq!((range_vec[10], range_vec[11]), (2, 3));
+
+    // A value evicted before the same write reaches its index is still restored
+    let mut range_vec: JournaledRangeVec<u8> =
+        RangeVec::with_max_span(4, OverflowPolicy::EvictFar).into();
+    range_vec.set(5, 7);
+    range_vec.mutate_many(0..=5, |_, v| *v += 1);
+    assert_eq!(range_vec.range_vec().range(), Some(2..=5));
+    assert_eq!(range_vec[5], 1);
+    assert!(range_vec.undo());
+    assert_eq!(range_vec.range_vec().range(), Some(5..=5));
+    assert_eq!(range_vec[5], 7);
+    assert!(range_vec.redo());
+    assert_eq!(range_vec.range_vec().range(), Some(2..=5));
+    assert_eq!(range_vec[5], 1);
+}
+
+#[test]
+#[should_panic(expected = "capacity overflow in RangeVec")]
+fn test_journaled_range_vec_unbounded_slices() {
+    let mut range_vec = JournaledRangeVec::<u8>::new();
+    range_vec.set(5, 1);
+    range_vec.make_contiguous_with(.., |slice| slice.fill(1));
+}
@@ -3,66 +3,160 @@
 //! ([`VecDeque`]) so that it may efficiently grow in either direction. It is useful for
 //! applications such as backing storage for scrolling data, and was originally designed for use in
 //! change tracking for an emulator's memory viewer.
+//!
+//! # Features
+//!
+//! - `serde`: Implements `Serialize` and `Deserialize` for [`RangeVec`]. Only the stored range is
+//!   serialized, as a struct with the fields `offset` and `data`, along with the default value in
+//!   the field `default`. Since `T` need not implement [`Default`], the `default` field is required
+//!   when deserializing.
 
 use std::{
-    collections::VecDeque,
+    cmp::Ordering,
+    collections::{BTreeMap, VecDeque},
     fmt::{Debug, Display},
-    ops::{Bound, Index, Range, RangeBounds},
+    hash::{Hash, Hasher},
+    ops::{Bound, Index, Range, RangeBounds, RangeInclusive},
 };
 
-pub use iter::Iter;
+pub use cow::CowRangeVec;
+pub use diff::{Diff, DiffSpan};
+pub use dirty::Checkpoint;
+pub use error::RangeVecError;
+pub use guard::RefMut;
+pub use index::RangeIndex;
+pub use iter::{IntoIter, Iter, IterMutGuard, NonDefault, Runs, Stored};
+pub use journal::JournaledRangeVec;
+pub use scrolling::ScrollingRangeVec;
+pub use segmented::{SegmentedIter, SegmentedRangeVec};
+pub use span::OverflowPolicy;
+pub use transaction::Transaction;
 
+use dirty::DirtyLog;
+use index::{add, offset_from, sub};
+use stamps::Stamps;
+
+mod cow;
+mod diff;
+mod dirty;
+mod error;
+mod guard;
+mod index;
 mod iter;
+mod journal;
+mod ops;
+mod scrolling;
+mod segmented;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod span;
+mod stamps;
+mod transaction;
 
 /// `RangeVec` is a data structure that will return a value for any index, but only a small range
 /// of values are non-default, and only these are stored. It is based on a ring buffer
 /// ([`VecDeque`]) so that it may efficiently grow in either direction.
 ///
-/// `RangeVec` requires that the stored type implement [`Default`] and [`Eq`], and it will return
-/// the default value whenever an index outside of its stored range is accessed. The stored range
-/// will automatically be grown or shrunk to exactly match the smallest possible range of
-/// non-default values after every mutation. To facilitate this, all mutable access is currently
-/// done through closures, so that the ring buffer may be adjusted based on whether the value is
-/// equal to `T::default()` after mutation. There may be a guard-based API in the future as well.
+/// Positions are of the index type `I`, which defaults to `usize` but may be any type implementing
+/// [`RangeIndex`], including all of the primitive integer types. For example, a `RangeVec<T, i64>`
+/// may grow left past zero. Every index up to and including `I::MAX` may hold a value, so the
+/// stored range returned by [`range`] is inclusive.
+///
+/// `RangeVec` requires that the stored type implement [`Eq`] and [`Clone`], and it will return a
+/// default value whenever an index outside of its stored range is accessed. This default value is
+/// `T::default()` for a `RangeVec` created with [`new`], or may be any sentinel value passed to
+/// [`with_default`]. The stored range will automatically be grown or shrunk to exactly match the
+/// smallest possible range of non-default values after every mutation. To facilitate this, all
+/// mutable access is done either through closures or through guards such as [`RefMut`], so that
+/// the ring buffer may be adjusted based on whether the value is equal to the default value after
+/// mutation.
+///
+/// Mutable iteration is available through [`iter_mut`], which returns an [`IterMutGuard`] that
+/// grows the backing storage up front and shrinks it once when dropped. The [`mutate_many`] or
+/// [`mutate_non_default`] methods may also work instead. The slice access methods
+/// [`as_mut_slices_with`] and [`make_contiguous_with`] may also be of interest.
+///
+/// `RangeVec` implements [`Index`], so you can get elements using square bracket syntax:
+/// `let x = my_range_vec[50];`. It does not implement [`IndexMut`], because
+/// [`IndexMut::index_mut`] must return a plain reference, and the ring buffer could not be
+/// adjusted after writing through it. Use [`get_mut`] instead: `*my_range_vec.get_mut(50) += 1;`.
+///
+/// `RangeVec` also implements the arithmetic and bitwise operators [`Add`], [`Sub`], [`BitOr`],
+/// [`BitAnd`] and [`BitXor`] (and their assigning forms) element-wise, computed over the union of
+/// both stored ranges as with [`zip_with`]. The default value of the result is the result of the
+/// operator on both default values.
+///
+/// Growing the backing storage can fail if a range is too large to allocate. The `try_` methods,
+/// such as [`try_mutate_many`] and [`try_make_contiguous_with`], return a [`RangeVecError`] in
+/// that case instead of panicking or aborting.
+///
+/// A `RangeVec` created with [`with_max_span`] caps the size of its stored range, evicting values
+/// or rejecting writes past it according to an [`OverflowPolicy`], which makes it suitable as
+/// bounded backing storage for scrolling data.
+///
+/// Writes may be tracked with [`checkpoint`] and [`dirty_since`], which report the spans written
+/// to through any mutating method since a checkpoint.
 ///
-/// Because of this, `RangeVec` currently has no `.iter_mut()` method, as without a guard it would
-/// not be possible to adjust the backing storage after a mutation. However, though less flexible,
-/// the [`mutate_many`] or [`mutate_non_default`] methods may work instead. The slice access methods
-/// [`as_mut_slices_with`] and [`make_contiguous_with`] may also be of interest. For the same
-/// reason, `RangeVec` implements [`Index`] so you can get elements using square bracked syntax:
-/// `let x = my_range_vec[50];`, but does not implement [`IndexMut`]. Again, this may be possible in
-/// the future using a guard API.
+/// Each stored value may also be stamped with the generation it was last written in, which is
+/// advanced with [`next_generation`] and queried with [`modified_at`]. Neither kind of tracking
+/// starts until the first checkpoint or generation is created, so a `RangeVec` which uses neither
+/// pays nothing for them.
 ///
 /// Because the backing storage is contiguous, this data structure is most efficient when all of
-/// the non-default values are within a small range. If they are sparse, consider using a map
+/// the non-default values are within a small range. If they are in a few clusters far apart from
+/// each other, consider using a [`SegmentedRangeVec`]. If they are sparse, consider using a map
 /// instead, particularly one with a hashing algorithm tuned for performance on integer indices.
 ///
-/// The stored type's implementation of [`Default`] should be fairly cheap, as it will be called
-/// frequently to initialize values before mutation of indices outside of the stored range, or to
-/// initialize default values between stored non-default values. It is a logic error for two calls
-/// to `T::default()` to return different results during the `RangeVec`'s lifetime.
+/// The stored type's implementation of [`Clone`] should be fairly cheap, as the default value will
+/// be cloned frequently to initialize values before mutation of indices outside of the stored
+/// range, or to initialize default values between stored non-default values.
 ///
 /// [`VecDeque`]: std::collections::vec_deque::VecDeque
-/// [`Default`]: std::default::Default
 /// [`Eq`]: std::cmp::Eq
+/// [`Clone`]: std::clone::Clone
 /// [`Index`]: std::ops::Index
 /// [`IndexMut`]: std::ops::IndexMut
+/// [`IndexMut::index_mut`]: std::ops::IndexMut::index_mut
+/// [`Add`]: std::ops::Add
+/// [`Sub`]: std::ops::Sub
+/// [`BitOr`]: std::ops::BitOr
+/// [`BitAnd`]: std::ops::BitAnd
+/// [`BitXor`]: std::ops::BitXor
 ///
+/// [`new`]: RangeVec::new
+/// [`range`]: RangeVec::range
+/// [`with_default`]: RangeVec::with_default
+/// [`with_max_span`]: RangeVec::with_max_span
+/// [`checkpoint`]: RangeVec::checkpoint
+/// [`dirty_since`]: RangeVec::dirty_since
+/// [`next_generation`]: RangeVec::next_generation
+/// [`modified_at`]: RangeVec::modified_at
+/// [`get_mut`]: RangeVec::get_mut
+/// [`iter_mut`]: RangeVec::iter_mut
 /// [`mutate_many`]: RangeVec::mutate_many
+/// [`try_mutate_many`]: RangeVec::try_mutate_many
+/// [`try_make_contiguous_with`]: RangeVec::try_make_contiguous_with
+/// [`zip_with`]: RangeVec::zip_with
 /// [`mutate_non_default`]: RangeVec::mutate_non_default
 /// [`as_mut_slices_with`]: RangeVec::as_mut_slices_with
 /// [`make_contiguous_with`]: RangeVec::make_contiguous_with
 ///
 #[derive(Debug, Clone)]
-pub struct RangeVec<T> {
+pub struct RangeVec<T, I = usize> {
     data: VecDeque<T>,
-    offset: usize,
+    offset: I,
     default_item: T,
+    max_span: Option<(usize, OverflowPolicy)>,
+    // `None` until the first checkpoint
+    dirty: Option<DirtyLog<I>>,
+    // `None` until the first generation, otherwise the same length as `data`
+    stamps: Option<Stamps>,
 }
 
-impl<T> Display for RangeVec<T>
+impl<T, I> Display for RangeVec<T, I>
 where
     T: Debug,
+    I: RangeIndex,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.range() {
@@ -76,30 +170,232 @@ where
     }
 }
 
-impl<T> Default for RangeVec<T>
+impl<T, I> Default for RangeVec<T, I>
 where
-    T: Default + Eq,
+    T: Default,
+    I: RangeIndex,
 {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<T> Index<usize> for RangeVec<T>
+/// Two `RangeVec`s are equal if they hold the same value at every index, regardless of how they
+/// were built. Because the stored range is always normalized, this only requires comparing the
+/// default values and the stored ranges. The default values are ignored if the stored range
+/// covers every index, since they aren't held at any index then.
+impl<T, I> PartialEq for RangeVec<T, I>
+where
+    T: PartialEq,
+    I: RangeIndex,
+{
+    fn eq(&self, other: &Self) -> bool {
+        (self.default_item == other.default_item || self.stores_every_index())
+            && self.data == other.data
+            && (self.data.is_empty() || self.offset == other.offset)
+    }
+}
+
+impl<T, I> Eq for RangeVec<T, I>
+where
+    T: Eq,
+    I: RangeIndex,
+{
+}
+
+impl<T, I> Hash for RangeVec<T, I>
+where
+    T: Hash,
+    I: Hash + RangeIndex,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        if !self.stores_every_index() {
+            self.default_item.hash(state);
+        }
+        self.data.hash(state);
+        if !self.data.is_empty() {
+            self.offset.hash(state);
+        }
+    }
+}
+
+/// `RangeVec`s are compared lexicographically by the value at every index, from `I::MIN` to
+/// `I::MAX`.
+impl<T, I> PartialOrd for RangeVec<T, I>
+where
+    T: PartialOrd,
+    I: RangeIndex,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.logical_cmp(other, T::partial_cmp)
+    }
+}
+
+impl<T, I> Ord for RangeVec<T, I>
+where
+    T: Ord,
+    I: RangeIndex,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.logical_cmp(other, |a, b| Some(a.cmp(b)))
+            .expect("total order")
+    }
+}
+
+impl<T, I> Index<I> for RangeVec<T, I>
 where
-    T: Default + Eq,
+    T: Eq + Clone,
+    I: RangeIndex,
 {
     type Output = T;
 
-    fn index(&self, index: usize) -> &Self::Output {
+    fn index(&self, index: I) -> &Self::Output {
         self.get(index)
     }
 }
 
-impl<T> RangeVec<T> {
+impl<T, I> IntoIterator for RangeVec<T, I>
+where
+    I: RangeIndex,
+{
+    type Item = (I, T);
+    type IntoIter = IntoIter<T, I>;
+
+    /// Creates an owning iterator over the stored range, emitting each stored value along with its
+    /// index. Default values inside of the stored range are included.
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter::new(self)
+    }
+}
+
+impl<'a, T, I> IntoIterator for &'a RangeVec<T, I>
+where
+    I: RangeIndex,
+{
+    type Item = (I, &'a T);
+    type IntoIter = Stored<'a, T, I>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.stored()
+    }
+}
+
+impl<T, I> FromIterator<(I, T)> for RangeVec<T, I>
+where
+    T: Default + Eq + Clone,
+    I: RangeIndex,
+{
+    /// Creates a `RangeVec` from `(index, value)` pairs. If an index appears more than once, the
+    /// last value is kept.
+    fn from_iter<V: IntoIterator<Item = (I, T)>>(iter: V) -> Self {
+        let mut range_vec = Self::new();
+        range_vec.extend(iter);
+        range_vec
+    }
+}
+
+impl<T, I> Extend<(I, T)> for RangeVec<T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    /// Sets each `(index, value)` pair, as if by calling [`set`](RangeVec::set) repeatedly.
+    fn extend<V: IntoIterator<Item = (I, T)>>(&mut self, iter: V) {
+        for (index, value) in iter {
+            self.set(index, value);
+        }
+    }
+}
+
+impl<T, I> RangeVec<T, I>
+where
+    I: RangeIndex,
+{
+    /// Creates an empty `RangeVec` where every index holds `default`. Unlike
+    /// [`new`](RangeVec::new), this does not require `T` to implement [`Default`], so it may be
+    /// used with a sentinel value such as `0xFF` for erased flash memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut range_vec: RangeVec<u8> = RangeVec::with_default(0xFF);
+    /// assert_eq!(range_vec.get(5), &0xFF);
+    ///
+    /// range_vec.set(5, 0);
+    /// range_vec.set(7, 0xFF);
+    /// assert_eq!(range_vec.range(), Some(5..=5));
+    /// assert_eq!(range_vec.get(5), &0);
+    /// ```
+    pub fn with_default(default: T) -> Self {
+        Self::from_parts(VecDeque::new(), I::MIN, default, None)
+    }
+
+    /// Creates an empty `RangeVec` where every index holds `default`, whose
+    /// [`range_size`](RangeVec::range_size) will never exceed `max_span`. This combines
+    /// [`with_default`](RangeVec::with_default) and [`with_max_span`](RangeVec::with_max_span).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_span` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::{OverflowPolicy, RangeVec};
+    /// let mut flash: RangeVec<u8> =
+    ///     RangeVec::with_default_and_max_span(0xFF, 4, OverflowPolicy::EvictFar);
+    /// flash.copy_from_slice(0, &[1, 2, 3, 4, 5]);
+    /// assert_eq!(flash.range(), Some(1..=4));
+    /// assert_eq!(flash[0], 0xFF);
+    /// ```
+    pub fn with_default_and_max_span(default: T, max_span: usize, policy: OverflowPolicy) -> Self {
+        assert!(max_span > 0, "maximum span of RangeVec must be nonzero");
+        Self::from_parts(VecDeque::new(), I::MIN, default, Some((max_span, policy)))
+    }
+
+    /// Creates a `RangeVec` storing `data` starting at `offset`, without any tracking. Every
+    /// constructor goes through this, so that new fields only need to be initialized here. The
+    /// caller is responsible for normalizing the stored range.
+    fn from_parts(
+        data: VecDeque<T>,
+        offset: I,
+        default: T,
+        max_span: Option<(usize, OverflowPolicy)>,
+    ) -> Self {
+        Self {
+            data,
+            offset,
+            default_item: default,
+            max_span,
+            dirty: None,
+            stamps: None,
+        }
+    }
+
+    /// Returns the maximum span and overflow policy set with
+    /// [`with_max_span`](RangeVec::with_max_span), if any.
+    pub fn max_span(&self) -> Option<(usize, OverflowPolicy)> {
+        self.max_span
+    }
+
+    /// Returns a reference to the default value, which is returned for any index outside of the
+    /// stored range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let range_vec: RangeVec<u32> = RangeVec::with_default(u32::MAX);
+    /// assert_eq!(range_vec.default_value(), &u32::MAX);
+    /// ```
+    pub fn default_value(&self) -> &T {
+        &self.default_item
+    }
+
     /// Returns the currently stored range of the internal buffer, exactly encompassing the
-    /// leftmost (inclusive) and rightmost (exclusive) non-default values. This will return `None`
-    /// if the range is empty, i.e., if there are no non-default values.
+    /// leftmost and rightmost non-default values, both inclusive. This will return `None` if the
+    /// range is empty, i.e., if there are no non-default values.
     ///
     /// # Examples
     ///
@@ -110,10 +406,10 @@ impl<T> RangeVec<T> {
     ///
     /// range_vec.set(5, 1);
     /// range_vec.set(10, 2);
-    /// assert_eq!(range_vec.range(), Some(5..11));
+    /// assert_eq!(range_vec.range(), Some(5..=10));
     /// ```
-    pub fn range(&self) -> Option<Range<usize>> {
-        (!self.is_empty()).then(|| self.offset..self.offset + self.data.len())
+    pub fn range(&self) -> Option<RangeInclusive<I>> {
+        (!self.is_empty()).then(|| self.offset..=add(self.offset, self.data.len() - 1))
     }
 
     /// Returns the size of the stored range.
@@ -150,8 +446,8 @@ impl<T> RangeVec<T> {
     }
 
     /// Creates an iterator over the specified range. A range unbounded on the left will start at
-    /// `0` (inclusive), and one unbounded on the right will end at `usize::MAX` (exclusive). The
-    /// iterator will emit values of type `&T`.
+    /// `I::MIN`, and one unbounded on the right will end at `I::MAX`, both inclusive. The iterator
+    /// will emit values of type `&T`.
     ///
     /// # Examples
     ///
@@ -163,10 +459,63 @@ impl<T> RangeVec<T> {
     /// let numbers: Vec<i32> = range_vec.iter(1..=7).copied().collect();
     /// assert_eq!(numbers, vec![0, 0, 1, 0, 2, 0, 0]);
     /// ```
-    pub fn iter(&self, range: impl RangeBounds<usize>) -> Iter<'_, T> {
+    pub fn iter(&self, range: impl RangeBounds<I>) -> Iter<'_, T, I> {
         Iter::new(self, range_bounds_to_range(range))
     }
 
+    /// Creates an iterator over the stored range, emitting each stored value along with its index.
+    /// Default values inside of the stored range are included. The iterator will emit values of
+    /// type `(I, &T)`. This is also the iterator created by iterating over `&RangeVec`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let range_vec: RangeVec<i32> = [(3, 1), (5, 2)].into_iter().collect();
+    /// let stored: Vec<(usize, i32)> = range_vec.stored().map(|(i, v)| (i, *v)).collect();
+    /// assert_eq!(stored, vec![(3, 1), (4, 0), (5, 2)]);
+    /// ```
+    pub fn stored(&self) -> Stored<'_, T, I> {
+        Stored::new(self)
+    }
+
+    /// Returns `true` if the stored range covers every index from `I::MIN` to `I::MAX`, so that
+    /// the default value isn't held at any index.
+    fn stores_every_index(&self) -> bool {
+        self.range()
+            .is_some_and(|range| *range.start() == I::MIN && *range.end() == I::MAX)
+    }
+
+    /// Lexicographically compares the values at every index of `self` and `other` using `f`.
+    fn logical_cmp<F>(&self, other: &Self, mut f: F) -> Option<Ordering>
+    where
+        F: FnMut(&T, &T) -> Option<Ordering>,
+    {
+        let Some(union) = range_union(self.range(), other.range()) else {
+            return f(&self.default_item, &other.default_item);
+        };
+
+        // Both are default left of the union
+        if *union.start() > I::MIN {
+            match f(&self.default_item, &other.default_item) {
+                Some(Ordering::Equal) => {}
+                ordering => return ordering,
+            }
+        }
+        for (a, b) in self.iter(union.clone()).zip(other.iter(union.clone())) {
+            match f(a, b) {
+                Some(Ordering::Equal) => {}
+                ordering => return ordering,
+            }
+        }
+        // Both are default right of the union
+        if *union.end() < I::MAX {
+            f(&self.default_item, &other.default_item)
+        } else {
+            Some(Ordering::Equal)
+        }
+    }
+
     /// Clears the `RangeVec`, resetting all values to default.
     ///
     /// # Examples
@@ -180,13 +529,200 @@ impl<T> RangeVec<T> {
     /// assert!(range_vec.is_empty());
     /// ```
     pub fn clear(&mut self) {
+        if let Some(range) = self.range() {
+            self.mark_written(range);
+        }
+        self.clear_data();
+    }
+
+    /// Clears the ring buffer and its stamps, without recording a write.
+    fn clear_data(&mut self) {
         self.data.clear();
+        if let Some(stamps) = &mut self.stamps {
+            stamps.clear();
+        }
+    }
+
+    /// Removes the values at `positions` from the ring buffer, along with their stamps.
+    fn drain_data(&mut self, positions: Range<usize>) {
+        self.data.drain(positions.clone());
+        if let Some(stamps) = &mut self.stamps {
+            stamps.drain(positions);
+        }
+    }
+
+    /// Creates a [`Checkpoint`] from which the spans written to may be queried with
+    /// [`dirty_since`](RangeVec::dirty_since). Writes are only tracked once the first checkpoint
+    /// has been created, as described on [`RangeVec`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut memory: RangeVec<u8> = RangeVec::new();
+    /// memory.set(0x10, 1);
+    /// let frame = memory.checkpoint();
+    /// memory.set(0x20, 2);
+    /// memory.copy_from_slice(0x21, &[3, 4]);
+    /// memory.reset(0x10);
+    /// let dirty: Vec<_> = memory.dirty_since(frame).collect();
+    /// assert_eq!(dirty, vec![0x10..=0x10, 0x20..=0x22]);
+    /// ```
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        self.dirty.get_or_insert_with(DirtyLog::new).checkpoint()
+    }
+
+    /// Returns the spans written to through any mutating method since `checkpoint`, sorted and
+    /// merged. The spans may include indices which were written to without changing their value,
+    /// such as every index passed to the closure of [`mutate_many`](RangeVec::mutate_many). If the
+    /// default value changed, the span covers every index.
+    pub fn dirty_since(&self, checkpoint: Checkpoint) -> impl Iterator<Item = RangeInclusive<I>> {
+        self.dirty
+            .as_ref()
+            .map(|dirty| dirty.since(checkpoint))
+            .unwrap_or_default()
+            .into_iter()
+    }
+
+    /// Discard the record of writes made before `checkpoint`, to free memory when older
+    /// checkpoints will no longer be queried. Querying them afterwards only reports the writes made
+    /// since `checkpoint`.
+    pub fn discard_dirty_before(&mut self, checkpoint: Checkpoint) {
+        if let Some(dirty) = &mut self.dirty {
+            dirty.discard_before(checkpoint);
+        }
+    }
+
+    /// Starts a new generation, and returns it. Every value written afterwards is stamped with
+    /// the new generation, which can be queried with [`modified_at`](RangeVec::modified_at). The
+    /// first call starts tracking, as described on [`RangeVec`], and values stored before then
+    /// have no stamp.
+    ///
+    /// The stamps are stored alongside the values, so they are dropped along with any value
+    /// which is reset to the default value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut memory: RangeVec<u8> = RangeVec::new();
+    /// memory.set(0x10, 1);
+    /// assert_eq!(memory.next_generation(), 1);
+    /// memory.set(0x11, 2);
+    /// assert_eq!(memory.next_generation(), 2);
+    /// memory.mutate_many(0x12..0x14, |_, v| *v = 3);
+    ///
+    /// assert_eq!(memory.modified_at(0x10), None);
+    /// assert_eq!(memory.modified_at(0x11), Some(1));
+    /// assert_eq!(memory.modified_at(0x13), Some(2));
+    /// assert_eq!(memory.generation(), Some(2));
+    /// ```
+    pub fn next_generation(&mut self) -> u64 {
+        let len = self.data.len();
+        self.stamps
+            .get_or_insert_with(|| Stamps::new(len))
+            .next_generation()
+    }
+
+    /// Returns the current generation, or `None` if
+    /// [`next_generation`](RangeVec::next_generation) has never been called.
+    pub fn generation(&self) -> Option<u64> {
+        self.stamps.as_ref().map(Stamps::generation)
+    }
+
+    /// Returns the generation the value at `index` was last written in, or `None` if it is not
+    /// stored or hasn't been written since generations started being tracked.
+    pub fn modified_at(&self, index: I) -> Option<u64> {
+        let position = index
+            .checked_offset_from(self.offset)
+            .filter(|&position| position < self.data.len())?;
+        self.stamps.as_ref()?.get(position)
+    }
+
+    /// Creates an iterator over the stored values last written in a generation after
+    /// `generation`, along with their indices. The iterator will emit values of type `(I, &T)`,
+    /// in order of increasing index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut memory: RangeVec<u8> = RangeVec::new();
+    /// let frame = memory.next_generation();
+    /// memory.copy_from_slice(0x10, &[1, 2, 3]);
+    /// memory.next_generation();
+    /// memory.set(0x11, 4);
+    /// let changed: Vec<_> = memory.iter_modified_after(frame).collect();
+    /// assert_eq!(changed, vec![(0x11, &4)]);
+    /// ```
+    pub fn iter_modified_after(&self, generation: u64) -> impl Iterator<Item = (I, &T)> {
+        self.stamps.iter().flat_map(move |stamps| {
+            self.data
+                .iter()
+                .zip(stamps.iter())
+                .enumerate()
+                .filter(move |(_, (_, &stamp))| stamp > generation)
+                .map(move |(i, (item, _))| (add(self.offset, i), item))
+        })
+    }
+
+    /// Records a write to `range`, if writes are being tracked, and stamps the stored values in it
+    /// with the current generation, if generations are being tracked.
+    fn mark_written(&mut self, range: RangeInclusive<I>) {
+        let positions = self.saturating_positions(&range);
+        if let Some(dirty) = &mut self.dirty {
+            dirty.mark(range);
+        }
+        if let Some(stamps) = &mut self.stamps {
+            stamps.stamp(positions);
+        }
+    }
+
+    /// Records a write to `index`, if writes are being tracked.
+    fn mark_written_index(&mut self, index: I) {
+        self.mark_written(index..=index);
+    }
+
+    /// Records a write to every index in `range` except the `discarded` ones, which must be
+    /// sorted, if writes are being tracked.
+    fn mark_written_except(&mut self, range: RangeInclusive<I>, discarded: &[I]) {
+        let (mut start, end) = range.into_inner();
+        for &index in discarded {
+            if index > start {
+                self.mark_written(start..=sub(index, 1));
+            }
+            match index.checked_add_usize(1) {
+                Some(next) => start = next,
+                None => return,
+            }
+        }
+        if start <= end {
+            self.mark_written(start..=end);
+        }
+    }
+
+    /// Returns the positions in the backing storage of the indices in `range`, clamped to the
+    /// stored range.
+    fn saturating_positions(&self, range: &RangeInclusive<I>) -> Range<usize> {
+        let len = self.data.len();
+        let start = match range.start().checked_offset_from(self.offset) {
+            Some(position) => position.min(len),
+            None if *range.start() < self.offset => 0,
+            None => len,
+        };
+        let end = match range.end().checked_offset_from(self.offset) {
+            Some(position) => position.saturating_add(1).min(len),
+            None if *range.end() < self.offset => 0,
+            None => len,
+        };
+        start..end.max(start)
     }
 }
 
-impl<T> RangeVec<T>
+impl<T, I> RangeVec<T, I>
 where
-    T: Default + Eq,
+    T: Default,
+    I: RangeIndex,
 {
     /// Creates an empty (all-default) `RangeVec`.
     ///
@@ -198,36 +734,184 @@ where
     /// assert_eq!(range_vec.range(), None);
     /// ```
     pub fn new() -> Self {
-        Self {
-            data: VecDeque::new(),
-            offset: 0,
-            default_item: T::default(),
+        Self::with_default(T::default())
+    }
+
+    /// Creates an empty (all-default) `RangeVec` whose [`range_size`](RangeVec::range_size) will
+    /// never exceed `max_span`. When a write would grow the stored range past it, `policy`
+    /// decides whether the write is rejected, discarded, or which values are evicted to make room.
+    ///
+    /// Methods that grow the ring buffer to include a whole range at once, such as
+    /// [`iter_mut`](RangeVec::iter_mut) or
+    /// [`make_contiguous_with`](RangeVec::make_contiguous_with), treat a range that doesn't fit as
+    /// a rejected write under any policy.
+    ///
+    /// To use a default value other than `T::default()`, use
+    /// [`with_default_and_max_span`](RangeVec::with_default_and_max_span).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_span` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::{OverflowPolicy, RangeVec};
+    /// let mut lines: RangeVec<u32> = RangeVec::with_max_span(3, OverflowPolicy::EvictFar);
+    /// for i in 1..=5 {
+    ///     lines.set(i as usize, i);
+    /// }
+    /// assert_eq!(lines.range(), Some(3..=5));
+    ///
+    /// let mut pinned: RangeVec<u32> = RangeVec::with_max_span(3, OverflowPolicy::Discard);
+    /// for i in 1..=5 {
+    ///     pinned.set(i as usize, i);
+    /// }
+    /// assert_eq!(pinned.range(), Some(1..=3));
+    /// ```
+    pub fn with_max_span(max_span: usize, policy: OverflowPolicy) -> Self {
+        Self::with_default_and_max_span(T::default(), max_span, policy)
+    }
+}
+
+impl<T, I> RangeVec<T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    /// Grows the ring buffer to include every index from `first` to `last` (inclusive), filling
+    /// new positions with the default value. The buffer is resized at most once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored range would not fit in a `usize`.
+    fn grow_to_include_range(&mut self, first: I, last: I) {
+        if !self
+            .make_room(first, last)
+            .unwrap_or_else(|error| panic!("{error}"))
+        {
+            panic!("{}", RangeVecError::SpanOverflow);
         }
+        let (left, right) = self
+            .growth(first, last)
+            .unwrap_or_else(|error| panic!("{error}"));
+        self.grow_by(first, left, right);
     }
 
-    fn grow_to_include(&mut self, index: usize) {
-        if self.data.is_empty() {
-            // Empty: set offset = index and insert value
-            self.offset = index;
-            self.data.push_back(T::default());
-        } else if index < self.offset {
-            // index < offset: grow left, set offset = index and insert value
-            let additional = self.offset - index;
-            self.data.reserve(additional);
-            for _ in 0..additional {
-                self.data.push_front(T::default());
+    /// Fallible version of [`grow_to_include_range`](RangeVec::grow_to_include_range), which
+    /// reserves the new positions with [`VecDeque::try_reserve`] before growing.
+    fn try_grow_to_include_range(&mut self, first: I, last: I) -> Result<(), RangeVecError> {
+        if !self.make_room(first, last)? {
+            return Err(RangeVecError::SpanOverflow);
+        }
+        let (left, right) = self.growth(first, last)?;
+        self.try_reserve(left + right)?;
+        self.grow_by(first, left, right);
+        Ok(())
+    }
+
+    /// Reserves room for `additional` more positions in the ring buffer and in the generation
+    /// stamps kept alongside it, if any, so that growing by that many can't fail.
+    fn try_reserve(&mut self, additional: usize) -> Result<(), RangeVecError> {
+        self.data.try_reserve(additional)?;
+        if let Some(stamps) = &mut self.stamps {
+            stamps.try_reserve(additional)?;
+        }
+        Ok(())
+    }
+
+    /// Makes room under the maximum span for every index from `first` to `last` (inclusive),
+    /// evicting stored values according to the [`OverflowPolicy`]. Returns `Ok(false)` if the
+    /// indices don't fit and a write to them should be discarded instead.
+    fn make_room(&mut self, first: I, last: I) -> Result<bool, RangeVecError> {
+        let Some((max_span, policy)) = self.max_span else {
+            return Ok(true);
+        };
+        let fits = |first: I, last: I| {
+            last.checked_offset_from(first)
+                .is_some_and(|distance| distance < max_span)
+        };
+        let grow_right = match self.range() {
+            Some(range) if fits(first.min(*range.start()), last.max(*range.end())) => {
+                return Ok(true)
             }
-            self.offset = index;
-        } else if index >= self.offset + self.data.len() {
-            // index >= offset + length: grow right and insert value
-            let additional = index - (self.offset + self.data.len()) + 1;
-            self.data.reserve(additional);
-            for _ in 0..additional {
-                self.data.push_back(T::default());
+            Some(range) => last > *range.end(),
+            None if fits(first, last) => return Ok(true),
+            None => false,
+        };
+
+        match policy {
+            OverflowPolicy::Reject => Err(RangeVecError::SpanOverflow),
+            OverflowPolicy::Discard => Ok(false),
+            OverflowPolicy::EvictFar if !fits(first, last) => Err(RangeVecError::SpanOverflow),
+            OverflowPolicy::EvictFar => {
+                if grow_right {
+                    // Growing right: keep the window ending at last
+                    let start = last.checked_sub_usize(max_span - 1).unwrap_or(I::MIN);
+                    self.truncate(start..);
+                } else {
+                    // Growing left: keep the window starting at first
+                    let end = first.checked_add_usize(max_span - 1).unwrap_or(I::MAX);
+                    self.truncate(..=end);
+                }
+                Ok(true)
             }
         }
     }
 
+    /// Returns the number of positions to add on the left and on the right of the ring buffer so
+    /// that it includes every index from `first` to `last` (inclusive).
+    fn growth(&self, first: I, last: I) -> Result<(usize, usize), RangeVecError> {
+        if self.data.is_empty() {
+            // Empty: the whole range is added on the right of the new offset
+            let len = last
+                .checked_offset_from(first)
+                .and_then(|distance| distance.checked_add(1))
+                .ok_or(RangeVecError::CapacityOverflow)?;
+            return Ok((0, len));
+        }
+
+        // first < offset: grow left
+        let left = if first < self.offset {
+            self.offset
+                .checked_offset_from(first)
+                .ok_or(RangeVecError::CapacityOverflow)?
+        } else {
+            0
+        };
+        // last >= offset + length: grow right
+        let right = match last.checked_offset_from(self.offset) {
+            Some(position) if position >= self.data.len() => position - self.data.len() + 1,
+            Some(_) => 0,
+            None if last < self.offset => 0,
+            None => return Err(RangeVecError::CapacityOverflow),
+        };
+        left.checked_add(right)
+            .and_then(|additional| additional.checked_add(self.data.len()))
+            .ok_or(RangeVecError::CapacityOverflow)?;
+        Ok((left, right))
+    }
+
+    /// Grows the ring buffer by `left` positions on the left and `right` positions on the right,
+    /// as computed by [`growth`](RangeVec::growth) for a range starting at `first`.
+    fn grow_by(&mut self, first: I, left: usize, right: usize) {
+        if self.data.is_empty() {
+            self.offset = first;
+        }
+        if left == 0 && right == 0 {
+            return;
+        }
+
+        // Extend at the back, then rotate the new values that belong on the left to the front
+        self.data
+            .resize(self.data.len() + left + right, self.default_item.clone());
+        self.data.rotate_right(left);
+        if let Some(stamps) = &mut self.stamps {
+            stamps.grow(left, right);
+        }
+        self.offset = self.offset.min(first);
+    }
+
     /// Provides a reference to the element at the given index, or to a default element.
     ///
     /// # Examples
@@ -239,9 +923,9 @@ where
     /// assert_eq!(range_vec.get(5), &1);
     /// assert_eq!(range_vec.get(10), &0);
     /// ```
-    pub fn get(&self, index: usize) -> &T {
+    pub fn get(&self, index: I) -> &T {
         match index
-            .checked_sub(self.offset)
+            .checked_offset_from(self.offset)
             .and_then(|index| self.data.get(index))
         {
             Some(item) => item,
@@ -252,10 +936,10 @@ where
     fn shrink_left(&mut self) {
         match self.data.iter().position(|item| item != &self.default_item) {
             Some(index) => {
-                self.data.drain(..index);
-                self.offset += index;
+                self.drain_data(0..index);
+                self.offset = add(self.offset, index);
             }
-            None => self.data.clear(),
+            None => self.clear_data(),
         }
     }
 
@@ -266,30 +950,56 @@ where
             .rposition(|item| item != &self.default_item)
         {
             Some(index) => {
-                self.data.drain(index + 1..);
+                self.drain_data(index + 1..self.data.len());
             }
-            None => self.data.clear(),
+            None => self.clear_data(),
         }
     }
 
-    fn shrink(&mut self, index: usize) {
+    fn shrink(&mut self, index: I) {
         if index == self.offset {
             self.shrink_left();
-        } else if index == self.offset + self.data.len() - 1 {
+        } else if offset_from(index, self.offset) == self.data.len() - 1 {
             self.shrink_right();
         }
     }
 
-    fn grow_and_set(&mut self, index: usize, value: T) {
-        if value != self.default_item {
-            self.grow_to_include(index);
-            self.data[index - self.offset] = value;
+    /// Writes `value` to `index` outside of the stored range. Returns `false` if the write was
+    /// discarded under the maximum span.
+    fn grow_and_set(&mut self, index: I, value: T) -> bool {
+        if value == self.default_item {
+            return true;
+        }
+        if !self
+            .make_room(index, index)
+            .unwrap_or_else(|error| panic!("{error}"))
+        {
+            return false;
+        }
+        self.grow_to_include_range(index, index);
+        let position = offset_from(index, self.offset);
+        self.data[position] = value;
+        true
+    }
+
+    /// Fallible version of [`grow_and_set`](RangeVec::grow_and_set).
+    fn try_grow_and_set(&mut self, index: I, value: T) -> Result<bool, RangeVecError> {
+        if value == self.default_item {
+            return Ok(true);
+        }
+        if !self.make_room(index, index)? {
+            return Ok(false);
         }
+        self.try_grow_to_include_range(index, index)?;
+        let position = offset_from(index, self.offset);
+        self.data[position] = value;
+        Ok(true)
     }
 
     /// Set the value at index `index`. If the element is outside of the stored range and is not
-    /// equal to `T::default()`, the ring buffer will be grown to accomodate it. If it is inside
-    /// the stored range and is equal to `T::default`, the ring buffer will be shrunk accordingly.
+    /// equal to the default value, the ring buffer will be grown to accomodate it. If it is inside
+    /// the stored range and is equal to the default value, the ring buffer will be shrunk
+    /// accordingly.
     ///
     /// # Examples
     ///
@@ -299,116 +1009,696 @@ where
     /// range_vec.set(5, 1);
     /// range_vec.set(7, 2);
     /// range_vec.set(9, 3);
-    /// assert_eq!(range_vec.range(), Some(5..10));
+    /// assert_eq!(range_vec.range(), Some(5..=9));
     ///
     /// range_vec.set(5, 0);
-    /// assert_eq!(range_vec.range(), Some(7..10));
+    /// assert_eq!(range_vec.range(), Some(7..=9));
     /// ```
-    pub fn set(&mut self, index: usize, value: T) {
-        match index
-            .checked_sub(self.offset)
+    pub fn set(&mut self, index: I, value: T) {
+        let written = match index
+            .checked_offset_from(self.offset)
             .and_then(|index| self.data.get_mut(index))
         {
             Some(item) => {
                 // index is inside the current range
                 *item = value;
                 self.shrink(index);
+                true
             }
             None => {
                 // index is outside the current range
-                self.grow_and_set(index, value);
+                self.grow_and_set(index, value)
             }
+        };
+        if written {
+            self.mark_written_index(index);
         }
     }
 
-    /// Mutate the value at index `index`. If the element is outside of the stored range and is not
-    /// equal to `T::default()` after mutation, the ring buffer will be grown to accomodate it. If
-    /// it is inside the stored range and is equal to `T::default` after mutation, the ring buffer
-    /// will be shrunk accordingly. Any value returned from the passed closure `f` will be returned
-    /// from the method.
+    /// Fallible version of [`set`](RangeVec::set). Returns an error instead of panicking or
+    /// aborting if the write is rejected by the [`OverflowPolicy`], or if the ring buffer can't be
+    /// grown to include `index`. In that case the value is not written.
     ///
     /// # Examples
     ///
     /// ```
-    /// # use range_vec::RangeVec;
-    /// let mut range_vec: RangeVec<i32> = RangeVec::new();
-    /// range_vec.get_mut_with(5, |v| *v = 1);
-    /// range_vec.get_mut_with(7, |v| *v += 2);
-    /// range_vec.get_mut_with(9, |v| *v = 3);
-    /// assert_eq!(range_vec.range(), Some(5..10));
+    /// # use range_vec::{RangeVec, RangeVecError};
+    /// let mut memory: RangeVec<u64, u64> = RangeVec::new();
+    /// memory.try_set(0x1000, 1)?;
     ///
-    /// range_vec.get_mut_with(5, |v| *v -= 1);
-    /// range_vec.get_mut_with(9, |v| *v = 0);
-    /// assert_eq!(range_vec.range(), Some(7..8));
+    /// // A stray write far away from the stored range
+    /// let result = memory.try_set(0xFFFF_FFFF_FFFF_0000, 2);
+    /// assert!(result.is_err());
+    /// assert_eq!(memory.range(), Some(0x1000..=0x1000));
+    /// # Ok::<(), RangeVecError>(())
     /// ```
-    pub fn get_mut_with<F, R>(&mut self, index: usize, f: F) -> R
-    where
-        F: FnOnce(&mut T) -> R,
-    {
-        match index
-            .checked_sub(self.offset)
+    pub fn try_set(&mut self, index: I, value: T) -> Result<(), RangeVecError> {
+        let written = match index
+            .checked_offset_from(self.offset)
             .and_then(|index| self.data.get_mut(index))
         {
             Some(item) => {
                 // index is inside the current range
-                let ret = f(item);
+                *item = value;
                 self.shrink(index);
-                ret
+                true
             }
             None => {
                 // index is outside the current range
-                let mut value = T::default();
-                let ret = f(&mut value);
-                self.grow_and_set(index, value);
-                ret
+                self.try_grow_and_set(index, value)?
             }
+        };
+        if written {
+            self.mark_written_index(index);
         }
+        Ok(())
     }
 
-    /// Mutate a range of values. This method is equivalent to calling
-    /// [`get_mut_with`](RangeVec::get_mut_with) repeatedly on an entire range of values, except it does not
-    /// pass through the closure's return value. In addition, it only makes the checks to grow and
-    /// shrink the backing storage once. The closure is also passed the index as its first
-    /// argument.
+    /// Reserve capacity in the ring buffer, and in the generation stamps if they are tracked, for
+    /// the stored range to grow to include `index`, so that a following write to it won't
+    /// allocate. Returns an error if the stored range would not fit in a `usize` or the allocation
+    /// fails. The stored range itself is unchanged, since it always exactly encompasses the
+    /// non-default values, and the maximum span is not checked.
     ///
     /// # Examples
     ///
     /// ```
-    /// # use range_vec::RangeVec;
-    /// let mut range_vec: RangeVec<i32> = RangeVec::new();
-    /// range_vec.mutate_many(5..15, |_, v| *v += 1);
-    /// range_vec.mutate_many(10..15, |_, v| *v -= 1);
-    /// range_vec.mutate_many(5..8, |i, v| *v += i as i32);
-    /// assert_eq!(range_vec.range(), Some(5..10));
-    /// assert_eq!(
-    ///     range_vec.iter(5..15).copied().collect::<Vec<i32>>(),
-    ///     vec![6, 7, 8, 1, 1, 0, 0, 0, 0, 0],
-    /// );
+    /// # use range_vec::{RangeVec, RangeVecError};
+    /// let mut memory: RangeVec<u8, u64> = RangeVec::new();
+    /// memory.set(0x1000, 1);
+    /// memory.try_grow_to_include(0x1FFF)?;
+    /// assert_eq!(memory.range(), Some(0x1000..=0x1000));
+    ///
+    /// // A stray address far away from the stored range
+    /// assert!(memory.try_grow_to_include(0xFFFF_FFFF_FFFF_0000).is_err());
+    /// # Ok::<(), RangeVecError>(())
+    /// ```
+    pub fn try_grow_to_include(&mut self, index: I) -> Result<(), RangeVecError> {
+        let (left, right) = self.growth(index, index)?;
+        self.try_reserve(left + right)?;
+        Ok(())
+    }
+
+    /// Set consecutive values starting at index `start`. The ring buffer is grown once, at the
+    /// first non-default value outside of the stored range, to include as many values as the
+    /// iterator's [`size_hint`](Iterator::size_hint) guarantees from there. Default values outside
+    /// of the stored range are skipped, as they are by [`mutate_many`](RangeVec::mutate_many). The
+    /// checks to shrink the backing storage are only made once, after all values are written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a non-default value would be written past `I::MAX`, or if the stored range would
+    /// not fit in a `usize`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut range_vec: RangeVec<i32> = RangeVec::new();
+    /// range_vec.set_range(5, [0, 1, 2, 0]);
+    /// assert_eq!(range_vec.range(), Some(6..=7));
+    ///
+    /// range_vec.set_range(3, (1..=3).map(|v| v * 10));
+    /// assert_eq!(range_vec.range(), Some(3..=7));
+    /// assert_eq!(
+    ///     range_vec.iter(3..8).copied().collect::<Vec<i32>>(),
+    ///     vec![10, 20, 30, 1, 2],
+    /// );
+    /// ```
+    pub fn set_range<V>(&mut self, start: I, values: V)
+    where
+        V: IntoIterator<Item = T>,
+    {
+        let mut values = values.into_iter().enumerate();
+        let mut last = None;
+        let mut discarded = Vec::new();
+        while let Some((i, value)) = values.next() {
+            let is_default = value == self.default_item;
+            let Some(index) = start.checked_add_usize(i) else {
+                // Writing the default value past I::MAX changes nothing
+                assert!(is_default, "index overflow in RangeVec");
+                continue;
+            };
+            last = Some(index);
+            match index
+                .checked_offset_from(self.offset)
+                .and_then(|position| self.data.get_mut(position))
+            {
+                Some(item) => *item = value,
+                None if is_default => {}
+                // Under a maximum span, each value may evict others, so they are grown one at a
+                // time
+                None if self.max_span.is_some() => {
+                    if !self.grow_and_set(index, value) {
+                        discarded.push(index);
+                    }
+                }
+                None => {
+                    let (remaining, _) = values.size_hint();
+                    let grown = index.checked_add_usize(remaining).unwrap_or(I::MAX);
+                    self.grow_to_include_range(index, grown);
+                    let position = offset_from(index, self.offset);
+                    self.data[position] = value;
+                }
+            }
+        }
+
+        if let Some(last) = last {
+            self.mark_written_except(start..=last, &discarded);
+        }
+        self.shrink_left();
+        self.shrink_right();
+    }
+
+    /// Set consecutive values starting at index `start` by cloning them from `values`. This is
+    /// equivalent to [`set_range`](RangeVec::set_range), but the ring buffer is always grown only
+    /// once, to include exactly the values from the first non-default one to the last.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a non-default value would be written past `I::MAX`, or if the stored range would
+    /// not fit in a `usize`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut range_vec: RangeVec<u8> = RangeVec::new();
+    /// range_vec.copy_from_slice(0x100, &[0xDE, 0xAD, 0xBE, 0xEF]);
+    /// assert_eq!(range_vec.range(), Some(0x100..=0x103));
+    /// assert_eq!(range_vec[0x102], 0xBE);
+    /// ```
+    pub fn copy_from_slice(&mut self, start: I, values: &[T]) {
+        let is_default = |value: &T| value == &self.default_item;
+        let first = values.iter().position(|value| !is_default(value));
+        let last = values.iter().rposition(|value| !is_default(value));
+        if let (Some(first), Some(last), None) = (first, last, self.max_span) {
+            self.grow_to_include_range(add(start, first), add(start, last));
+        }
+        self.set_range(start, values.iter().cloned());
+    }
+
+    /// Mutate the value at index `index`. If the element is outside of the stored range and is not
+    /// equal to the default value after mutation, the ring buffer will be grown to accomodate it.
+    /// If it is inside the stored range and is equal to the default value after mutation, the ring
+    /// buffer will be shrunk accordingly. Any value returned from the passed closure `f` will be
+    /// returned from the method.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut range_vec: RangeVec<i32> = RangeVec::new();
+    /// range_vec.get_mut_with(5, |v| *v = 1);
+    /// range_vec.get_mut_with(7, |v| *v += 2);
+    /// range_vec.get_mut_with(9, |v| *v = 3);
+    /// assert_eq!(range_vec.range(), Some(5..=9));
+    ///
+    /// range_vec.get_mut_with(5, |v| *v -= 1);
+    /// range_vec.get_mut_with(9, |v| *v = 0);
+    /// assert_eq!(range_vec.range(), Some(7..=7));
+    /// ```
+    pub fn get_mut_with<F, R>(&mut self, index: I, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let (ret, written) = match index
+            .checked_offset_from(self.offset)
+            .and_then(|index| self.data.get_mut(index))
+        {
+            Some(item) => {
+                // index is inside the current range
+                let ret = f(item);
+                self.shrink(index);
+                (ret, true)
+            }
+            None => {
+                // index is outside the current range
+                let mut value = self.default_item.clone();
+                let ret = f(&mut value);
+                (ret, self.grow_and_set(index, value))
+            }
+        };
+        if written {
+            self.mark_written_index(index);
+        }
+        ret
+    }
+
+    /// Fallible version of [`get_mut_with`](RangeVec::get_mut_with). Returns an error if the
+    /// mutated value is not the default value and the ring buffer can't be grown to include
+    /// `index`. In that case `f` has already been called, but the mutated value and the closure's
+    /// return value are discarded and `self` is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::{RangeVec, RangeVecError};
+    /// let mut range_vec: RangeVec<i32, u64> = RangeVec::new();
+    /// assert_eq!(range_vec.try_get_mut_with(5, |v| { *v += 1; *v }), Ok(1));
+    ///
+    /// // Reading never grows the ring buffer
+    /// assert_eq!(range_vec.try_get_mut_with(u64::MAX, |v| *v), Ok(0));
+    /// // Far too large to allocate
+    /// assert!(range_vec.try_get_mut_with(u64::MAX, |v| *v = 1).is_err());
+    /// assert_eq!(range_vec.range(), Some(5..=5));
+    /// ```
+    pub fn try_get_mut_with<F, R>(&mut self, index: I, f: F) -> Result<R, RangeVecError>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let (ret, written) = match index
+            .checked_offset_from(self.offset)
+            .and_then(|index| self.data.get_mut(index))
+        {
+            Some(item) => {
+                // index is inside the current range
+                let ret = f(item);
+                self.shrink(index);
+                (ret, true)
+            }
+            None => {
+                // index is outside the current range
+                let mut value = self.default_item.clone();
+                let ret = f(&mut value);
+                (ret, self.try_grow_and_set(index, value)?)
+            }
+        };
+        if written {
+            self.mark_written_index(index);
+        }
+        Ok(ret)
+    }
+
+    /// Mutably access the value at index `index` through a guard. When the guard is dropped, the
+    /// ring buffer will be grown or shrunk in the same way as
+    /// [`get_mut_with`](RangeVec::get_mut_with). If `index` is outside of the stored range, the
+    /// guard holds a copy of the default value which is only inserted if it has been changed when
+    /// the guard is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Under a maximum span with [`OverflowPolicy::Reject`], panics if `index` is outside of the
+    /// maximum span, even if the value is never changed. This is checked here rather than when
+    /// the guard is dropped, so that dropping the guard while unwinding can't abort.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut range_vec: RangeVec<i32> = RangeVec::new();
+    /// *range_vec.get_mut(5) = 1;
+    /// *range_vec.get_mut(7) += 2;
+    /// *range_vec.get_mut(9) = 3;
+    /// assert_eq!(range_vec.range(), Some(5..=9));
+    ///
+    /// *range_vec.get_mut(5) -= 1;
+    /// *range_vec.get_mut(9) = 0;
+    /// assert_eq!(range_vec.range(), Some(7..=7));
+    /// ```
+    pub fn get_mut(&mut self, index: I) -> RefMut<'_, T, I> {
+        RefMut::new(self, index)
+    }
+
+    /// Mutate a range of values. This method is equivalent to calling
+    /// [`get_mut_with`](RangeVec::get_mut_with) repeatedly on an entire range of values, except it
+    /// does not pass through the closure's return value. In addition, it only makes the checks to
+    /// grow and shrink the backing storage once. The closure is also passed the index as its first
+    /// argument.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut range_vec: RangeVec<i32> = RangeVec::new();
+    /// range_vec.mutate_many(5..15, |_, v| *v += 1);
+    /// range_vec.mutate_many(10..15, |_, v| *v -= 1);
+    /// range_vec.mutate_many(5..8, |i, v| *v += i as i32);
+    /// assert_eq!(range_vec.range(), Some(5..=9));
+    /// assert_eq!(
+    ///     range_vec.iter(5..15).copied().collect::<Vec<i32>>(),
+    ///     vec![6, 7, 8, 1, 1, 0, 0, 0, 0, 0],
+    /// );
     /// ```
-    pub fn mutate_many<F>(&mut self, range: impl RangeBounds<usize>, mut f: F)
+    pub fn mutate_many<F>(&mut self, range: impl RangeBounds<I>, mut f: F)
     where
-        F: FnMut(usize, &mut T),
+        F: FnMut(I, &mut T),
     {
         let range = range_bounds_to_range(range);
-        for i in range {
+        let mut discarded = Vec::new();
+        for i in indices(range.clone()) {
             if let Some(item) = i
-                .checked_sub(self.offset)
+                .checked_offset_from(self.offset)
                 .and_then(|index| self.data.get_mut(index))
             {
                 f(i, item);
             } else {
-                let mut value = T::default();
+                let mut value = self.default_item.clone();
                 f(i, &mut value);
-                self.grow_and_set(i, value);
+                if !self.grow_and_set(i, value) {
+                    discarded.push(i);
+                }
             }
         }
+        if let Some(range) = range {
+            self.mark_written_except(range, &discarded);
+        }
         self.shrink_left();
         self.shrink_right();
     }
 
+    /// Fallible version of [`mutate_many`](RangeVec::mutate_many). Returns an error if growing
+    /// the backing storage fails, in which case the values before the failing index have already
+    /// been mutated. The backing storage is shrunk as appropriate either way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::{RangeVec, RangeVecError};
+    /// let mut range_vec: RangeVec<i32, u64> = RangeVec::new();
+    /// range_vec.try_mutate_many(5..=7, |_, v| *v += 1)?;
+    /// assert_eq!(range_vec.range(), Some(5..=7));
+    ///
+    /// // Far too large to allocate
+    /// let result = range_vec.try_mutate_many(u64::MAX - 1.., |_, v| *v = 1);
+    /// assert!(result.is_err());
+    /// assert_eq!(range_vec.range(), Some(5..=7));
+    /// # Ok::<(), RangeVecError>(())
+    /// ```
+    pub fn try_mutate_many<F>(
+        &mut self,
+        range: impl RangeBounds<I>,
+        mut f: F,
+    ) -> Result<(), RangeVecError>
+    where
+        F: FnMut(I, &mut T),
+    {
+        let range = range_bounds_to_range(range);
+        let mut discarded = Vec::new();
+        let result = indices(range.clone()).try_for_each(|i| {
+            if let Some(item) = i
+                .checked_offset_from(self.offset)
+                .and_then(|index| self.data.get_mut(index))
+            {
+                f(i, item);
+            } else {
+                let mut value = self.default_item.clone();
+                f(i, &mut value);
+                if !self.try_grow_and_set(i, value)? {
+                    discarded.push(i);
+                }
+            }
+            Ok(())
+        });
+        if let Some(range) = range {
+            self.mark_written_except(range, &discarded);
+        }
+        self.shrink_left();
+        self.shrink_right();
+        result
+    }
+
+    /// Run `f` with a [`Transaction`] which stages writes to `self`. If `f` returns `Ok`, every
+    /// staged write is applied at once, only making the checks to grow and shrink the backing
+    /// storage once, in the same way as [`mutate_many`](RangeVec::mutate_many). If it returns
+    /// `Err`, the staged writes are discarded and `self` is left untouched. The result of `f` is
+    /// returned from the method.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the staged writes can't all be applied, as described for
+    /// [`try_transaction`](RangeVec::try_transaction). `self` is left untouched in that case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut memory: RangeVec<u8> = RangeVec::new();
+    /// memory.copy_from_slice(0x100, &[1, 2, 3]);
+    ///
+    /// let result = memory.transaction(|tx| {
+    ///     tx.set(0x100, 0xFF);
+    ///     if tx[0x101] != 0 {
+    ///         return Err("address 0x101 is in use");
+    ///     }
+    ///     tx.set(0x101, 0xFF);
+    ///     Ok(())
+    /// });
+    /// assert!(result.is_err());
+    /// assert_eq!(memory[0x100], 1);
+    ///
+    /// memory.transaction(|tx| {
+    ///     tx.copy_from_slice(0x200, &[4, 5]);
+    ///     tx.reset(0x100);
+    ///     Ok::<(), ()>(())
+    /// })?;
+    /// assert_eq!(memory.range(), Some(0x101..=0x201));
+    /// # Ok::<(), ()>(())
+    /// ```
+    pub fn transaction<F, R, E>(&mut self, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut Transaction<'_, T, I>) -> Result<R, E>,
+    {
+        let mut transaction = Transaction::new(self);
+        let ret = f(&mut transaction)?;
+        let staged = transaction.into_staged();
+        self.try_apply_staged(staged)
+            .unwrap_or_else(|error| panic!("{error}"));
+        Ok(ret)
+    }
+
+    /// Fallible version of [`transaction`](RangeVec::transaction). Returns an error if the staged
+    /// writes can't all be applied, in which case none of them are and `self` is left untouched.
+    ///
+    /// Under a maximum span, the stored range after the transaction must fit in it. With
+    /// [`OverflowPolicy::EvictFar`], stored values are evicted from the far end to make room as
+    /// for a single write, but the staged values themselves must fit. With the other policies, a
+    /// transaction that doesn't fit fails with [`RangeVecError::SpanOverflow`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::{OverflowPolicy, RangeVec, RangeVecError};
+    /// let mut memory: RangeVec<u8> = RangeVec::with_max_span(4, OverflowPolicy::Reject);
+    /// memory.set(0, 1);
+    ///
+    /// let result = memory.try_transaction(|tx| {
+    ///     tx.set(1, 2);
+    ///     tx.set(10, 3);
+    ///     Ok(())
+    /// });
+    /// assert_eq!(result, Err(RangeVecError::SpanOverflow));
+    /// assert_eq!(memory.range(), Some(0..=0));
+    /// ```
+    pub fn try_transaction<F, R, E>(&mut self, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut Transaction<'_, T, I>) -> Result<R, E>,
+        E: From<RangeVecError>,
+    {
+        let mut transaction = Transaction::new(self);
+        let ret = f(&mut transaction)?;
+        let staged = transaction.into_staged();
+        self.try_apply_staged(staged)?;
+        Ok(ret)
+    }
+
+    /// Writes every staged value, growing the ring buffer once to include all of the non-default
+    /// ones, then shrinks once. The maximum span and the allocation are checked before any value
+    /// is written, so either every staged value is written or none are.
+    fn try_apply_staged(&mut self, staged: BTreeMap<I, T>) -> Result<(), RangeVecError> {
+        // The stored values which are not overwritten bound the result along with the staged ones
+        let mut kept = self
+            .non_default()
+            .map(|(index, _)| index)
+            .filter(|index| !staged.contains_key(index));
+        let kept_range = kept
+            .next()
+            .map(|first| first..=kept.next_back().unwrap_or(first));
+        let (resets, writes): (Vec<_>, Vec<_>) = staged
+            .into_iter()
+            .partition(|(_, value)| value == &self.default_item);
+        let written_range = writes
+            .first()
+            .zip(writes.last())
+            .map(|((first, _), (last, _))| *first..=*last);
+
+        // The range the stored range must fit in once every value is written
+        let mut window = None;
+        let mut target = range_union(kept_range, written_range.clone());
+        if let (Some((max_span, policy)), Some(range)) = (self.max_span, &target) {
+            let fits = |range: &RangeInclusive<I>| {
+                range
+                    .end()
+                    .checked_offset_from(*range.start())
+                    .is_some_and(|distance| distance < max_span)
+            };
+            if !fits(range) {
+                let (OverflowPolicy::EvictFar, Some(written_range)) = (policy, &written_range)
+                else {
+                    return Err(RangeVecError::SpanOverflow);
+                };
+                if !fits(written_range) {
+                    return Err(RangeVecError::SpanOverflow);
+                }
+                let (first, last) = written_range.clone().into_inner();
+                let grow_right = self.range().is_some_and(|range| last > *range.end());
+                window = Some(if grow_right {
+                    // Growing right: keep the window ending at last
+                    last.checked_sub_usize(max_span - 1).unwrap_or(I::MIN)..=last
+                } else {
+                    // Growing left: keep the window starting at first
+                    first..=first.checked_add_usize(max_span - 1).unwrap_or(I::MAX)
+                });
+                target = window.clone();
+            }
+        }
+        if let Some(target) = target {
+            let len = target
+                .end()
+                .checked_offset_from(*target.start())
+                .and_then(|distance| distance.checked_add(1))
+                .ok_or(RangeVecError::CapacityOverflow)?;
+            self.try_reserve(len.saturating_sub(self.data.len()))?;
+        }
+
+        // Nothing can fail from here on
+        if let Some(window) = window {
+            self.truncate_to(Some(window));
+        }
+        // Reset first, so that the stored range is within the target before growing
+        for (index, value) in resets {
+            if let Some(item) = index
+                .checked_offset_from(self.offset)
+                .and_then(|position| self.data.get_mut(position))
+            {
+                *item = value;
+            }
+            self.mark_written_index(index);
+        }
+        self.shrink_left();
+        self.shrink_right();
+
+        if let Some(written_range) = written_range {
+            self.grow_to_include_range(*written_range.start(), *written_range.end());
+            for (index, value) in writes {
+                let position = offset_from(index, self.offset);
+                self.data[position] = value;
+                self.mark_written_index(index);
+            }
+        }
+        self.shrink_left();
+        self.shrink_right();
+        Ok(())
+    }
+
+    /// Mutably iterate over the specified range. Like [`iter`](RangeVec::iter), a range unbounded
+    /// on the left will start at `I::MIN`, and one unbounded on the right will end at `I::MAX`,
+    /// both inclusive.
+    ///
+    /// This method returns an [`IterMutGuard`], which is not itself an iterator. The ring buffer is
+    /// grown to include the entire range when the guard is created, the same way as
+    /// [`mutate_many`](RangeVec::mutate_many) materializes default values for indices outside of
+    /// the stored range, and is shrunk once when the guard is dropped. Call
+    /// [`IterMutGuard::iter`] or iterate over `&mut IterMutGuard` to access the elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut range_vec: RangeVec<i32> = RangeVec::new();
+    /// range_vec.set(5, -5);
+    /// range_vec
+    ///     .iter_mut(3..8)
+    ///     .iter()
+    ///     .zip([1, 2, 3, 4, 5])
+    ///     .for_each(|(v, x)| *v += x);
+    /// assert_eq!(range_vec.range(), Some(3..=7));
+    /// assert_eq!(
+    ///     range_vec.iter(3..8).copied().collect::<Vec<i32>>(),
+    ///     vec![1, 2, -2, 4, 5],
+    /// );
+    ///
+    /// for v in &mut range_vec.iter_mut(3..7) {
+    ///     *v = 0;
+    /// }
+    /// assert_eq!(range_vec.range(), Some(7..=7));
+    /// ```
+    pub fn iter_mut(&mut self, range: impl RangeBounds<I>) -> IterMutGuard<'_, T, I> {
+        IterMutGuard::new(self, range_bounds_to_range(range))
+    }
+
+    /// Creates an iterator over the maximal runs of equal values within the specified range.
+    /// Ranges are handled the same way as in [`iter`](RangeVec::iter). The iterator will emit
+    /// values of type `(RangeInclusive<I>, &T)`, containing the indices of each run and the value
+    /// repeated in it. The runs of default values outside of the stored range are each emitted in
+    /// a single step, no matter how long they are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut range_vec: RangeVec<u8, u32> = RangeVec::new();
+    /// range_vec.copy_from_slice(0x1000, &[1, 1, 2]);
+    /// let runs: Vec<_> = range_vec.runs(..).collect();
+    /// assert_eq!(
+    ///     runs,
+    ///     vec![
+    ///         (0..=0xFFF, &0),
+    ///         (0x1000..=0x1001, &1),
+    ///         (0x1002..=0x1002, &2),
+    ///         (0x1003..=u32::MAX, &0),
+    ///     ],
+    /// );
+    /// ```
+    pub fn runs(&self, range: impl RangeBounds<I>) -> Runs<'_, T, I> {
+        Runs::new(self, range_bounds_to_range(range))
+    }
+
+    /// Creates an iterator over all values that are not the default value, along with their
+    /// indices. The iterator will emit values of type `(I, &T)`, in order of increasing index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut range_vec: RangeVec<i32> = RangeVec::new();
+    /// range_vec.set(3, 1);
+    /// range_vec.set(5, 2);
+    /// let changed: Vec<(usize, i32)> = range_vec.non_default().map(|(i, v)| (i, *v)).collect();
+    /// assert_eq!(changed, vec![(3, 1), (5, 2)]);
+    /// ```
+    pub fn non_default(&self) -> NonDefault<'_, T, I> {
+        self.iter_non_default(..)
+    }
+
+    /// Creates an iterator over all values within the specified range that are not the default
+    /// value, along with their indices. Ranges are handled the same way as in
+    /// [`iter`](RangeVec::iter), but only the stored range is visited, so iterating over a large
+    /// range is cheap. The iterator will emit values of type `(I, &T)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut range_vec: RangeVec<i32> = RangeVec::new();
+    /// range_vec.set(3, 1);
+    /// range_vec.set(5, 2);
+    /// range_vec.set(9, 3);
+    /// let changed: Vec<(usize, i32)> = range_vec
+    ///     .iter_non_default(4..)
+    ///     .rev()
+    ///     .map(|(i, v)| (i, *v))
+    ///     .collect();
+    /// assert_eq!(changed, vec![(9, 3), (5, 2)]);
+    /// ```
+    pub fn iter_non_default(&self, range: impl RangeBounds<I>) -> NonDefault<'_, T, I> {
+        NonDefault::new(self, range_bounds_to_range(range))
+    }
+
     /// Mutate all values that are not the default value. This method will call `f` repeatedly on
-    /// each element `v` where `v != T::default()`, and only makes the checks to grow and shrink the
-    /// backing storage once. The closure is also passed the index as its first argument.
+    /// each element `v` that is not equal to the default value, and only makes the checks to grow
+    /// and shrink the backing storage once. The closure is also passed the index as its first
+    /// argument.
     ///
     /// # Examples
     ///
@@ -420,7 +1710,7 @@ where
     /// range_vec.set(9, 2);
     ///
     /// range_vec.mutate_non_default(|i, v| *v += i as i32);
-    /// assert_eq!(range_vec.range(), Some(7..10));
+    /// assert_eq!(range_vec.range(), Some(7..=9));
     /// assert_eq!(
     ///     range_vec.iter(7..10).copied().collect::<Vec<i32>>(),
     ///     vec![8, 0, 11],
@@ -428,79 +1718,304 @@ where
     /// ```
     pub fn mutate_non_default<F>(&mut self, mut f: F)
     where
-        F: FnMut(usize, &mut T),
+        F: FnMut(I, &mut T),
     {
+        if let Some(range) = self.range() {
+            self.mark_written(range);
+        }
         for (i, item) in self.data.iter_mut().enumerate() {
             if item != &self.default_item {
-                f(i + self.offset, item);
+                f(add(self.offset, i), item);
             }
         }
         self.shrink_left();
         self.shrink_right();
     }
 
-    /// Reset the value at a given index to `T::default()`, and shrink the backing storage
-    /// accordingly. If `index` is outside the stored range, this method is a no-op.
+    /// Reset the value at a given index to the default value, and shrink the backing storage
+    /// accordingly. If `index` is outside the stored range, this method is a no-op.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut range_vec: RangeVec<i32> = RangeVec::new();
+    /// range_vec.set(5, 1);
+    /// range_vec.set(7, 2);
+    /// range_vec.set(9, 3);
+    ///
+    /// range_vec.reset(7);
+    /// assert_eq!(range_vec.range(), Some(5..=9));
+    /// assert_eq!(range_vec.get(7), &0);
+    ///
+    /// range_vec.reset(5);
+    /// assert_eq!(range_vec.range(), Some(9..=9));
+    /// ```
+    pub fn reset(&mut self, index: I) {
+        if let Some(item) = index
+            .checked_offset_from(self.offset)
+            .and_then(|index| self.data.get_mut(index))
+        {
+            *item = self.default_item.clone();
+            self.shrink(index);
+            self.mark_written_index(index);
+        }
+    }
+
+    /// Reset all values outside of `range` to the default value, and shrink the backing storage
+    /// accordingly.
+    ///
+    /// # Examples
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut range_vec: RangeVec<i32> = RangeVec::new();
+    /// range_vec.set(5, 1);
+    /// range_vec.set(7, 2);
+    /// range_vec.set(9, 3);
+    ///
+    /// range_vec.truncate(6..);
+    /// assert_eq!(range_vec.range(), Some(7..=9));
+    ///
+    /// range_vec.truncate(15..20);
+    /// assert!(range_vec.is_empty());
+    pub fn truncate(&mut self, range: impl RangeBounds<I>) {
+        self.truncate_to(range_bounds_to_range(range));
+    }
+
+    /// Reset all values outside of `range` to the default value, where `None` is an empty range.
+    fn truncate_to(&mut self, range: Option<RangeInclusive<I>>) {
+        let (Some(range), Some(stored)) = (range, self.range()) else {
+            // Clear the entire buffer if the range is empty
+            self.clear();
+            return;
+        };
+        let (start, end) = range.clone().into_inner();
+        let (stored_start, stored_end) = stored.into_inner();
+        if start > stored_start {
+            self.mark_written(stored_start..=sub(start, 1).min(stored_end));
+        }
+        if end < stored_end {
+            self.mark_written(add(end, 1).max(stored_start)..=stored_end);
+        }
+
+        let kept = self.saturating_positions(&range);
+        self.drain_data(kept.end..self.data.len());
+        self.drain_data(0..kept.start);
+        if !self.data.is_empty() {
+            self.offset = add(self.offset, kept.start);
+        }
+
+        self.shrink_left();
+        self.shrink_right();
+    }
+
+    /// Move every value `k` indices to the left. This only adjusts the offset of the stored range,
+    /// so it takes constant time, except that values which would move past `I::MIN` are dropped
+    /// first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut range_vec: RangeVec<u8, u16> = RangeVec::new();
+    /// range_vec.copy_from_slice(0x100, &[1, 2, 3]);
+    /// range_vec.shift_left(0xFF);
+    /// assert_eq!(range_vec.range(), Some(1..=3));
+    ///
+    /// range_vec.shift_left(2);
+    /// assert_eq!(range_vec.range(), Some(0..=1));
+    /// assert_eq!(range_vec[0], 2);
+    /// ```
+    pub fn shift_left(&mut self, k: usize) {
+        match I::MIN.checked_add_usize(k) {
+            Some(limit) => self.truncate(limit..),
+            None => self.clear(),
+        }
+        if let Some(range) = self.range() {
+            // Both the values' old and new positions are written to
+            self.mark_written(range.clone());
+            self.offset = sub(self.offset, k);
+            self.mark_written(sub(*range.start(), k)..=sub(*range.end(), k));
+        }
+    }
+
+    /// Move every value `k` indices to the right. This only adjusts the offset of the stored
+    /// range, so it takes constant time, except that values which would move past `I::MAX` are
+    /// dropped first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut range_vec: RangeVec<u8, u8> = RangeVec::new();
+    /// range_vec.copy_from_slice(10, &[1, 2, 3]);
+    /// range_vec.shift_right(240);
+    /// assert_eq!(range_vec.range(), Some(250..=252));
+    ///
+    /// range_vec.shift_right(4);
+    /// assert_eq!(range_vec.range(), Some(254..=255));
+    /// assert_eq!(range_vec[255], 2);
+    /// ```
+    pub fn shift_right(&mut self, k: usize) {
+        match I::MAX.checked_sub_usize(k) {
+            Some(limit) => self.truncate(..=limit),
+            None => self.clear(),
+        }
+        if let Some(range) = self.range() {
+            // Both the values' old and new positions are written to
+            self.mark_written(range.clone());
+            self.offset = add(self.offset, k);
+            self.mark_written(add(*range.start(), k)..=add(*range.end(), k));
+        }
+    }
+
+    /// Creates an iterator over the maximal spans of indices where `self` and `other` hold
+    /// different values. Each [`DiffSpan`] contains the range of the span, and iterators over the
+    /// old values (from `self`) and the new values (from `other`) in it. Runs of indices where
+    /// both are default are skipped without visiting each index.
     ///
     /// # Examples
     ///
     /// ```
     /// # use range_vec::RangeVec;
-    /// let mut range_vec: RangeVec<i32> = RangeVec::new();
-    /// range_vec.set(5, 1);
-    /// range_vec.set(7, 2);
-    /// range_vec.set(9, 3);
+    /// let mut before: RangeVec<u8> = RangeVec::new();
+    /// before.copy_from_slice(0x100, &[1, 2, 3, 4]);
+    /// let mut after = before.clone();
+    /// after.copy_from_slice(0x101, &[5, 6]);
+    /// after.set(0xFFFF, 7);
     ///
-    /// range_vec.reset(7);
-    /// assert_eq!(range_vec.range(), Some(5..10));
-    /// assert_eq!(range_vec.get(7), &0);
+    /// let spans: Vec<_> = before.diff(&after).map(|span| span.range).collect();
+    /// assert_eq!(spans, vec![0x101..=0x102, 0xFFFF..=0xFFFF]);
     ///
-    /// range_vec.reset(5);
-    /// assert_eq!(range_vec.range(), Some(9..10));
+    /// let span = before.diff(&after).next().unwrap();
+    /// assert_eq!(span.old.copied().collect::<Vec<u8>>(), vec![2, 3]);
+    /// assert_eq!(span.new.copied().collect::<Vec<u8>>(), vec![5, 6]);
     /// ```
-    pub fn reset(&mut self, index: usize) {
-        if let Some(item) = index
-            .checked_sub(self.offset)
-            .and_then(|index| self.data.get_mut(index))
-        {
-            *item = T::default();
-            self.shrink(index);
-        }
+    pub fn diff<'a>(&'a self, other: &'a RangeVec<T, I>) -> Diff<'a, T, I> {
+        Diff::new(self, other)
     }
 
-    /// Reset all values outside of `range` to `T::default()`, and shrink the backing storage
-    /// accordingly.
+    /// Combine `self` and `other` element-wise into a new `RangeVec`, by calling `f` on the values
+    /// at each index. `f` is only called for indices within the union of both stored ranges, and
+    /// once on both default values to determine the default value of the result. The result is
+    /// normalized.
     ///
     /// # Examples
+    ///
     /// ```
     /// # use range_vec::RangeVec;
-    /// let mut range_vec: RangeVec<i32> = RangeVec::new();
-    /// range_vec.set(5, 1);
-    /// range_vec.set(7, 2);
-    /// range_vec.set(9, 3);
+    /// let mut before: RangeVec<u8> = RangeVec::new();
+    /// before.copy_from_slice(5, &[1, 2, 3]);
+    /// let mut after = before.clone();
+    /// after.set(6, 4);
+    /// after.set(20, 5);
     ///
-    /// range_vec.truncate(6..);
-    /// assert_eq!(range_vec.range(), Some(7..10));
+    /// let changed = before.zip_with(&after, |a, b| a != b);
+    /// assert_eq!(changed.range(), Some(6..=20));
+    /// assert!(changed[6] && changed[20]);
+    /// assert!(!changed[7] && !changed[1000]);
+    /// ```
+    pub fn zip_with<U, V, F>(&self, other: &RangeVec<U, I>, mut f: F) -> RangeVec<V, I>
+    where
+        V: Eq + Clone,
+        F: FnMut(&T, &U) -> V,
+    {
+        let mut result = RangeVec::with_default(f(&self.default_item, &other.default_item));
+        if let Some(union) = range_union(self.range(), other.range()) {
+            result.offset = *union.start();
+            result.data = self
+                .iter(union.clone())
+                .zip(other.iter(union))
+                .map(|(a, b)| f(a, b))
+                .collect();
+            result.shrink_left();
+            result.shrink_right();
+        }
+        result
+    }
+
+    /// Combine `other` into `self` element-wise in place, by calling `f` on the values at each
+    /// index. `f` is only called for indices within the union of both stored ranges, and once on
+    /// both default values, which updates the default value of `self`. The checks to grow and
+    /// shrink the backing storage are only made once.
     ///
-    /// range_vec.truncate(15..20);
-    /// assert!(range_vec.is_empty());
-    pub fn truncate(&mut self, range: impl RangeBounds<usize>) {
-        let range = range_bounds_to_range(range);
-        if range.is_empty() {
-            // Clear the entire buffer if the range is empty
-            self.data.clear();
+    /// Under a maximum span, the combined values are instead written one at a time as with
+    /// [`set`](RangeVec::set), so each write follows the [`OverflowPolicy`]. Values between the
+    /// two stored ranges are skipped, since they combine two default values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the union of both stored ranges would not fit in a `usize`, or under a maximum
+    /// span with [`OverflowPolicy::Reject`] if a combined value would be written past it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::RangeVec;
+    /// let mut counts: RangeVec<u32> = RangeVec::new();
+    /// counts.copy_from_slice(5, &[1, 1]);
+    /// let mut hits: RangeVec<bool> = RangeVec::new();
+    /// hits.set(6, true);
+    /// hits.set(8, true);
+    ///
+    /// counts.merge_from(&hits, |count, &hit| *count += hit as u32);
+    /// assert_eq!(
+    ///     counts.iter(5..9).copied().collect::<Vec<u32>>(),
+    ///     vec![1, 2, 0, 1],
+    /// );
+    /// ```
+    pub fn merge_from<U, F>(&mut self, other: &RangeVec<U, I>, mut f: F)
+    where
+        F: FnMut(&mut T, &U),
+    {
+        let old_default = self.default_item.clone();
+        if self.max_span.is_some() {
+            f(&mut self.default_item, &other.default_item);
+            if self.default_item != old_default {
+                self.mark_written(I::MIN..=I::MAX);
+            }
+            // Visit the stored ranges in order, without the gap between them
+            let (first, second) = match (self.range(), other.range()) {
+                (Some(a), Some(b)) if b.start() < a.start() => (Some(b), Some(a)),
+                (a, b) => (a, b),
+            };
+            let second = match (&first, second) {
+                (Some(first), Some(second)) if second.start() <= first.end() => first
+                    .end()
+                    .checked_add_usize(1)
+                    .filter(|start| start <= second.end())
+                    .map(|start| start..=*second.end()),
+                (_, second) => second,
+            };
+            for range in [first, second].into_iter().flatten() {
+                for (index, other_item) in indices(Some(range.clone())).zip(other.iter(range)) {
+                    // Values outside of the stored range still hold the old default value
+                    let mut value = index
+                        .checked_offset_from(self.offset)
+                        .and_then(|position| self.data.get(position))
+                        .unwrap_or(&old_default)
+                        .clone();
+                    f(&mut value, other_item);
+                    self.set(index, value);
+                }
+            }
+            self.shrink_left();
+            self.shrink_right();
             return;
         }
 
-        // Drain right of range
-        let new_end = range.end.saturating_sub(self.offset).min(self.data.len());
-        self.data.drain(new_end..);
-
-        // Drain left of range
-        let new_start = range.start.saturating_sub(self.offset).min(self.data.len());
-        self.data.drain(..new_start);
-        self.offset += new_start;
-
+        if let Some(union) = range_union(self.range(), other.range()) {
+            // Grow while the buffer is still filled with the old default value
+            self.grow_to_include_range(*union.start(), *union.end());
+            self.mark_written(union.clone());
+            for (item, other_item) in self.data.iter_mut().zip(other.iter(union)) {
+                f(item, other_item);
+            }
+        }
+        f(&mut self.default_item, &other.default_item);
+        if self.default_item != old_default {
+            self.mark_written(I::MIN..=I::MAX);
+        }
         self.shrink_left();
         self.shrink_right();
     }
@@ -513,43 +2028,56 @@ where
     ///
     /// If you need to access a single contiguous slice and don't care about paying the cost to
     /// rearrange the backing storage, use [`make_contiguous_with`](RangeVec::make_contiguous_with).
-    pub fn as_mut_slices_with<F, R>(&mut self, range: impl RangeBounds<usize>, f: F) -> R
+    pub fn as_mut_slices_with<F, R>(&mut self, range: impl RangeBounds<I>, f: F) -> R
     where
         F: FnOnce(&mut [T], &mut [T]) -> R,
     {
         let range = range_bounds_to_range(range);
-        if range.is_empty() {
-            return f(&mut [], &mut []);
+        if let Some(range) = &range {
+            self.grow_to_include_range(*range.start(), *range.end());
         }
+        self.as_mut_slices_with_grown(range, f)
+    }
 
-        self.grow_to_include(range.start);
-        self.grow_to_include(range.end);
-        let data_len = self.data.len();
-        let (mut left, mut right) = self.data.as_mut_slices();
-        let split_point = self.offset + left.len();
-
-        if let Some(overlap) = split_point.checked_sub(range.end) {
-            right = &mut [];
-            let left_len = left.len();
-            left = &mut left[..left_len - overlap];
-        } else if let Some(overlap) = (self.offset + data_len).checked_sub(range.end) {
-            let right_len = right.len();
-            right = &mut right[..right_len - overlap];
+    /// Fallible version of [`as_mut_slices_with`](RangeVec::as_mut_slices_with). Returns an error
+    /// without calling `f` if growing the backing storage fails.
+    pub fn try_as_mut_slices_with<F, R>(
+        &mut self,
+        range: impl RangeBounds<I>,
+        f: F,
+    ) -> Result<R, RangeVecError>
+    where
+        F: FnOnce(&mut [T], &mut [T]) -> R,
+    {
+        let range = range_bounds_to_range(range);
+        if let Some(range) = &range {
+            self.try_grow_to_include_range(*range.start(), *range.end())?;
         }
+        Ok(self.as_mut_slices_with_grown(range, f))
+    }
 
-        if let Some(overlap) = range.start.checked_sub(split_point) {
-            left = &mut [];
-            right = &mut right[overlap..];
-        } else if let Some(overlap) = range.start.checked_sub(self.offset) {
-            left = &mut left[overlap..];
-        }
+    /// Calls `f` on the backing storage for `range`, which the ring buffer must already include,
+    /// and shrinks afterwards.
+    fn as_mut_slices_with_grown<F, R>(&mut self, range: Option<RangeInclusive<I>>, f: F) -> R
+    where
+        F: FnOnce(&mut [T], &mut [T]) -> R,
+    {
+        let Some(range) = range else {
+            return f(&mut [], &mut []);
+        };
 
-        if left.is_empty() && !right.is_empty() {
-            left = right;
-            right = &mut [];
-        }
+        let Range { start, end } = self.saturating_positions(&range);
+        self.mark_written(range);
+        let (left, right) = self.data.as_mut_slices();
+        let split_point = left.len();
 
-        let ret = f(left, right);
+        let ret = if end <= split_point {
+            f(&mut left[start..end], &mut [])
+        } else if start >= split_point {
+            f(&mut right[start - split_point..end - split_point], &mut [])
+        } else {
+            f(&mut left[start..], &mut right[..end - split_point])
+        };
         self.shrink_left();
         self.shrink_right();
         ret
@@ -561,33 +2089,69 @@ where
     ///
     /// If you don't want to pay the cost to rearrange the backing storage but are okay with the
     /// data being split up into two slices, use [`as_mut_slices_with`](RangeVec::as_mut_slices_with).
-    pub fn make_contiguous_with<F, R>(&mut self, range: impl RangeBounds<usize>, f: F) -> R
+    pub fn make_contiguous_with<F, R>(&mut self, range: impl RangeBounds<I>, f: F) -> R
     where
         F: FnOnce(&mut [T]) -> R,
     {
         let range = range_bounds_to_range(range);
-        if range.is_empty() {
-            return f(&mut []);
+        if let Some(range) = &range {
+            self.grow_to_include_range(*range.start(), *range.end());
+        }
+        self.make_contiguous_with_grown(range, f)
+    }
+
+    /// Fallible version of [`make_contiguous_with`](RangeVec::make_contiguous_with). Returns an
+    /// error without calling `f` if growing the backing storage fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::{RangeVec, RangeVecError};
+    /// let mut range_vec: RangeVec<u8> = RangeVec::new();
+    /// range_vec.try_make_contiguous_with(4..8, |slice| slice.fill(1))?;
+    /// assert_eq!(range_vec.range(), Some(4..=7));
+    ///
+    /// // Far too large to allocate
+    /// assert!(range_vec.try_make_contiguous_with(.., |slice| slice.len()).is_err());
+    /// assert_eq!(range_vec.range(), Some(4..=7));
+    /// # Ok::<(), RangeVecError>(())
+    /// ```
+    pub fn try_make_contiguous_with<F, R>(
+        &mut self,
+        range: impl RangeBounds<I>,
+        f: F,
+    ) -> Result<R, RangeVecError>
+    where
+        F: FnOnce(&mut [T]) -> R,
+    {
+        let range = range_bounds_to_range(range);
+        if let Some(range) = &range {
+            self.try_grow_to_include_range(*range.start(), *range.end())?;
         }
+        Ok(self.make_contiguous_with_grown(range, f))
+    }
+
+    /// Calls `f` on the backing storage for `range`, which the ring buffer must already include,
+    /// and shrinks afterwards.
+    fn make_contiguous_with_grown<F, R>(&mut self, range: Option<RangeInclusive<I>>, f: F) -> R
+    where
+        F: FnOnce(&mut [T]) -> R,
+    {
+        let Some(range) = range else {
+            return f(&mut []);
+        };
 
-        self.grow_to_include(range.start);
-        self.grow_to_include(range.end);
+        let Range { start, end } = self.saturating_positions(&range);
+        self.mark_written(range);
 
         let (left, right) = self.data.as_mut_slices();
-        let ret = if range.end - self.offset <= left.len() {
-            f(left)
-        } else if range.start - self.offset >= left.len() {
-            f(right)
+        let split_point = left.len();
+        let ret = if end <= split_point {
+            f(&mut left[start..end])
+        } else if start >= split_point {
+            f(&mut right[start - split_point..end - split_point])
         } else {
-            let data_len = self.data.len();
-            let mut slice = self.data.make_contiguous();
-            if let Some(overlap) = (self.offset + data_len).checked_sub(range.end) {
-                slice = &mut slice[..data_len - overlap];
-            }
-            if let Some(overlap) = range.start.checked_sub(self.offset) {
-                slice = &mut slice[overlap..];
-            }
-            f(slice)
+            f(&mut self.data.make_contiguous()[start..end])
         };
 
         self.shrink_left();
@@ -596,23 +2160,65 @@ where
     }
 }
 
-fn range_bounds_to_range(range_bounds: impl RangeBounds<usize>) -> Range<usize> {
+/// Converts `range_bounds` to an inclusive range, or `None` if it is empty. An unbounded start or
+/// end becomes `I::MIN` or `I::MAX`.
+fn range_bounds_to_range<I: RangeIndex>(
+    range_bounds: impl RangeBounds<I>,
+) -> Option<RangeInclusive<I>> {
     let start = match range_bounds.start_bound() {
-        Bound::Excluded(bound) => *bound + 1,
+        Bound::Excluded(bound) => bound.checked_add_usize(1)?,
         Bound::Included(bound) => *bound,
-        Bound::Unbounded => 0,
+        Bound::Unbounded => I::MIN,
     };
     let end = match range_bounds.end_bound() {
-        Bound::Excluded(bound) => *bound,
-        Bound::Included(bound) => *bound + 1,
-        Bound::Unbounded => usize::MAX,
+        Bound::Excluded(bound) => bound.checked_sub_usize(1)?,
+        Bound::Included(bound) => *bound,
+        Bound::Unbounded => I::MAX,
     };
-    start..end
+    (start <= end).then_some(start..=end)
+}
+
+/// Returns the indices in both `a` and `b`, or `None` if there are none.
+fn range_intersection<I: RangeIndex>(
+    a: &RangeInclusive<I>,
+    b: &RangeInclusive<I>,
+) -> Option<RangeInclusive<I>> {
+    let start = *a.start().max(b.start());
+    let end = *a.end().min(b.end());
+    (start <= end).then_some(start..=end)
+}
+
+/// Returns the smallest range containing both `a` and `b`, or `None` if both are `None`.
+fn range_union<I: RangeIndex>(
+    a: Option<RangeInclusive<I>>,
+    b: Option<RangeInclusive<I>>,
+) -> Option<RangeInclusive<I>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(*a.start().min(b.start())..=*a.end().max(b.end())),
+        (range, None) | (None, range) => range,
+    }
+}
+
+/// Returns an iterator over every index in `range`, which may be `None` for an empty range.
+///
+/// # Panics
+///
+/// Panics if the distance between the ends of `range` does not fit in a `usize`.
+fn indices<I: RangeIndex>(range: Option<RangeInclusive<I>>) -> impl DoubleEndedIterator<Item = I> {
+    range
+        .map(|range| {
+            let (start, end) = range.into_inner();
+            (0..=offset_from(end, start)).map(move |i| add(start, i))
+        })
+        .into_iter()
+        .flatten()
 }
 
 #[cfg(test)]
 mod test {
-    use super::RangeVec;
+    use std::ops::Bound;
+
+    use super::{OverflowPolicy, RangeVec, RangeVecError};
 
     #[test]
     fn test_get_index() {
@@ -633,10 +2239,10 @@ mod test {
         assert_eq!(range_vec.get(5), &10);
         assert_eq!(range_vec.get(10), &20);
         assert_eq!(range_vec[12], 0);
-        assert_eq!(range_vec.range(), Some(3..11));
+        assert_eq!(range_vec.range(), Some(3..=10));
 
         range_vec.get_mut_with(3, |v| *v = 0);
-        assert_eq!(range_vec.range(), Some(5..11));
+        assert_eq!(range_vec.range(), Some(5..=10));
         range_vec.set(10, 0);
         range_vec.set(5, 0);
         assert!(range_vec.is_empty());
@@ -647,6 +2253,181 @@ mod test {
         assert!(range_vec.is_empty());
     }
 
+    #[test]
+    fn test_with_default() {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        enum Mapping {
+            Unmapped,
+            Mapped(u32),
+        }
+
+        let mut range_vec = RangeVec::with_default(Mapping::Unmapped);
+        range_vec.set(5, Mapping::Mapped(1));
+        range_vec.get_mut_with(7, |v| *v = Mapping::Mapped(2));
+        *range_vec.get_mut(9) = Mapping::Mapped(3);
+        assert_eq!(range_vec.range(), Some(5..=9));
+        assert_eq!(range_vec[6], Mapping::Unmapped);
+        assert_eq!(range_vec[7], Mapping::Mapped(2));
+
+        range_vec.mutate_many(5..8, |_, v| *v = Mapping::Unmapped);
+        assert_eq!(range_vec.range(), Some(9..=9));
+        range_vec.reset(9);
+        assert!(range_vec.is_empty());
+    }
+
+    #[test]
+    fn test_signed_index() {
+        let mut range_vec = RangeVec::<u8, i64>::new();
+        range_vec.set(2, 1);
+        range_vec.set(-3, 2);
+        assert_eq!(range_vec.range(), Some(-3..=2));
+        assert_eq!(range_vec[-3], 2);
+        assert_eq!(range_vec[i64::MIN], 0);
+
+        range_vec.mutate_many(-5..-3, |i, v| *v = (-i) as u8);
+        assert_eq!(range_vec.range(), Some(-5..=2));
+        assert_eq!(
+            range_vec.iter(-6..-2).copied().collect::<Vec<_>>(),
+            vec![0, 5, 4, 2]
+        );
+
+        range_vec.truncate(-4..);
+        assert_eq!(range_vec.range(), Some(-4..=2));
+        range_vec.make_contiguous_with(-4..0, |slice| slice.fill(0));
+        assert_eq!(range_vec.range(), Some(2..=2));
+    }
+
+    #[test]
+    fn test_from_iter_extend() {
+        let mut range_vec: RangeVec<i32, i64> =
+            [(3, 1), (-2, 2), (3, 0), (4, 3)].into_iter().collect();
+        assert_eq!(range_vec.range(), Some(-2..=4));
+        assert_eq!(range_vec[3], 0);
+
+        range_vec.extend([(-2, 0), (10, 4)]);
+        assert_eq!(range_vec.range(), Some(4..=10));
+        assert_eq!(
+            range_vec
+                .into_iter()
+                .filter(|(_, v)| *v != 0)
+                .collect::<Vec<_>>(),
+            vec![(4, 3), (10, 4)]
+        );
+    }
+
+    #[test]
+    fn test_eq_ord_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let hash = |range_vec: &RangeVec<i32>| {
+            let mut hasher = DefaultHasher::new();
+            range_vec.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let mut a = RangeVec::<i32>::new();
+        a.set(5, 1);
+        a.set(9, 2);
+        let mut b = RangeVec::<i32>::new();
+        b.set(9, 2);
+        b.set(2, 3);
+        b.set(5, 1);
+        b.set(2, 0);
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        assert_eq!(a.cmp(&b), std::cmp::Ordering::Equal);
+
+        b.set(7, -1);
+        assert_ne!(a, b);
+        assert!(a > b);
+        b.set(7, 0);
+        b.set(20, 1);
+        assert!(a < b);
+
+        a.clear();
+        b.set(2, 5);
+        b.clear();
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        assert_ne!(a, RangeVec::with_default(1));
+        assert!(a < RangeVec::with_default(1));
+
+        // The default value isn't held anywhere once every index is stored
+        let full = |default| {
+            let mut range_vec = RangeVec::<u8, u8>::with_default(default);
+            range_vec.set_range(0, [5; 256]);
+            range_vec
+        };
+        let (a, b) = (full(0), full(1));
+        assert_eq!(a.cmp(&b), std::cmp::Ordering::Equal);
+        assert_eq!(a, b);
+        let hash = |range_vec: &RangeVec<u8, u8>| {
+            let mut hasher = DefaultHasher::new();
+            range_vec.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&a), hash(&b));
+    }
+
+    #[test]
+    fn test_zip_with_merge_from() {
+        let mut a = RangeVec::<i32, i64>::new();
+        a.copy_from_slice(-2, &[1, 2, 3]);
+        let mut b = RangeVec::<i32, i64>::with_default(1);
+        b.copy_from_slice(-1, &[0, 5]);
+        b.set(10, 2);
+
+        let sum = a.zip_with(&b, |a, b| a + b);
+        assert_eq!(sum.default_value(), &1);
+        assert_eq!(sum.range(), Some(-2..=10));
+        assert_eq!(sum[-2], 2);
+        assert_eq!(sum[-1], 2);
+        assert_eq!(sum[0], 8);
+        assert_eq!(sum[1], 1);
+        assert_eq!(sum[10], 2);
+
+        let product = a.zip_with(&b, |a, b| a * b);
+        assert_eq!(product.default_value(), &0);
+        assert_eq!(product.range(), Some(-2..=0));
+
+        a.merge_from(&b, |a, b| *a += b);
+        assert_eq!(a, sum);
+
+        // Under a maximum span, each combined value follows the overflow policy
+        let mut other = RangeVec::<i32, i64>::new();
+        other.copy_from_slice(0, &[1, 2]);
+        other.set(10, 3);
+        let mut evict = RangeVec::<i32, i64>::with_max_span(4, OverflowPolicy::EvictFar);
+        evict.set(-1, 4);
+        evict.merge_from(&other, |a, b| *a += b);
+        assert_eq!(evict.range(), Some(10..=10));
+        assert_eq!(evict[10], 3);
+        let mut discard = RangeVec::<i32, i64>::with_max_span(4, OverflowPolicy::Discard);
+        discard.set(-1, 4);
+        discard.merge_from(&other, |a, b| *a += b);
+        assert_eq!(discard.range(), Some(-1..=1));
+        assert_eq!(
+            discard.iter(-1..=1).copied().collect::<Vec<_>>(),
+            vec![4, 1, 2]
+        );
+
+        // Values equal to the old default value are kept if the default value changes
+        let mut shifted = RangeVec::<i32, i64>::with_max_span(4, OverflowPolicy::Reject);
+        other.reset(10);
+        shifted.merge_from(&other, |a, b| *a = b - 1);
+        assert_eq!(shifted.default_value(), &-1);
+        assert_eq!(shifted.range(), Some(0..=1));
+        assert_eq!(
+            shifted.iter(-1..=1).copied().collect::<Vec<_>>(),
+            vec![-1, 0, 1]
+        );
+
+        let mut empty = RangeVec::<i32, i64>::new();
+        empty.merge_from(&RangeVec::new(), |a, b: &i32| *a += b);
+        assert!(empty.is_empty());
+    }
+
     #[test]
     fn test_display() {
         let mut range_vec = RangeVec::<u8>::new();
@@ -656,7 +2437,7 @@ mod test {
         range_vec.set(9, 3);
         assert_eq!(
             format!("{}", range_vec),
-            "RangeVec { range: 5..10, data: [1, 0, 2, 0, 3] }"
+            "RangeVec { range: 5..=9, data: [1, 0, 2, 0, 3] }"
         );
     }
 
@@ -666,20 +2447,62 @@ mod test {
         range_vec.set(5, -1);
         range_vec.set(7, 1);
         range_vec.mutate_many(5..9, |_, value| *value += 1);
-        assert_eq!(range_vec.range(), Some(6..9));
+        assert_eq!(range_vec.range(), Some(6..=8));
         assert_eq!(
             range_vec.iter(6..9).copied().collect::<Vec<_>>(),
             vec![1, 2, 1]
         );
     }
 
+    #[test]
+    fn test_set_range() {
+        let mut range_vec = RangeVec::<i32>::new();
+        range_vec.set(10, 1);
+        range_vec.copy_from_slice(4, &[0, 0, 2, 3]);
+        assert_eq!(range_vec.range(), Some(6..=10));
+
+        // An iterator without a size hint grows the buffer as it goes
+        range_vec.set_range(9, [0, 0, 0, 5, 0].into_iter().filter(|_| true));
+        assert_eq!(range_vec.range(), Some(6..=12));
+        assert_eq!(
+            range_vec.iter(6..13).copied().collect::<Vec<_>>(),
+            vec![2, 3, 0, 0, 0, 0, 5]
+        );
+
+        range_vec.set_range(0, std::iter::repeat_n(0, 20));
+        assert!(range_vec.is_empty());
+        range_vec.set_range(0, std::iter::empty());
+        assert!(range_vec.is_empty());
+
+        // Default values outside of the stored range don't grow the buffer
+        let mut memory = RangeVec::<u8, u64>::new();
+        memory.set(0x1000, 1);
+        memory.copy_from_slice(0xFFFF_FFFF_FFFF_0000, &[0; 16]);
+        memory.set_range(0xFFFF_FFFF_FFFF_0000, std::iter::repeat_n(0, 16));
+        assert_eq!(memory.range(), Some(0x1000..=0x1000));
+
+        // Only default values may be written past I::MAX
+        let mut range_vec = RangeVec::<u8, u8>::new();
+        range_vec.copy_from_slice(u8::MAX, &[1, 0]);
+        range_vec.set_range(u8::MAX - 1, [2, 3, 0].into_iter().filter(|_| true));
+        assert_eq!(range_vec.range(), Some(u8::MAX - 1..=u8::MAX));
+        assert_eq!(range_vec[u8::MAX], 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "index overflow in RangeVec")]
+    fn test_set_range_overflow() {
+        let mut range_vec = RangeVec::<u8, u8>::new();
+        range_vec.copy_from_slice(u8::MAX, &[0, 1]);
+    }
+
     #[test]
     fn test_mutate_non_default() {
         let mut range_vec = RangeVec::<i32>::new();
         range_vec.set(5, -1);
         range_vec.set(7, 1);
         range_vec.mutate_non_default(|_, value| *value += 1);
-        assert_eq!(range_vec.range(), Some(7..8));
+        assert_eq!(range_vec.range(), Some(7..=7));
         assert_eq!(range_vec[7], 2);
     }
 
@@ -692,12 +2515,37 @@ mod test {
         range_vec.set(8, 8);
         range_vec.set(9, 9);
         range_vec.truncate(6..9);
-        assert_eq!(range_vec.range(), Some(6..9));
+        assert_eq!(range_vec.range(), Some(6..=8));
 
         range_vec.truncate(10..15);
         assert!(range_vec.is_empty());
     }
 
+    #[test]
+    fn test_grow() {
+        let mut range_vec = RangeVec::<i32>::new();
+        range_vec.set(10, 1);
+        range_vec.set(2, 2);
+        range_vec.set(14, 3);
+        assert_eq!(range_vec.range(), Some(2..=14));
+        assert_eq!(
+            range_vec.iter(2..15).copied().collect::<Vec<_>>(),
+            vec![2, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 3]
+        );
+
+        range_vec.as_mut_slices_with(0..20, |left, right| {
+            assert_eq!(left.len() + right.len(), 20);
+            let mut items = left.iter_mut().chain(right.iter_mut());
+            *items.next().unwrap() = 4;
+            *items.last().unwrap() = 5;
+        });
+        assert_eq!(range_vec.range(), Some(0..=19));
+        assert_eq!(range_vec[0], 4);
+        assert_eq!(range_vec[2], 2);
+        assert_eq!(range_vec[14], 3);
+        assert_eq!(range_vec[19], 5);
+    }
+
     #[test]
     fn test_as_mut_slices_with() {
         let mut range_vec = RangeVec::<i32>::new();
@@ -711,7 +2559,7 @@ mod test {
                 *item += 1;
             }
         });
-        assert_eq!(range_vec.range(), Some(3..9));
+        assert_eq!(range_vec.range(), Some(3..=8));
         assert_eq!(
             range_vec.iter(3..9).copied().collect::<Vec<_>>(),
             vec![1, 1, 6, 7, 8, 9]
@@ -731,10 +2579,335 @@ mod test {
                 *item += 1;
             }
         });
-        assert_eq!(range_vec.range(), Some(3..9));
+        assert_eq!(range_vec.range(), Some(3..=8));
         assert_eq!(
             range_vec.iter(3..9).copied().collect::<Vec<_>>(),
             vec![1, 1, 6, 7, 8, 9]
         );
     }
+
+    #[test]
+    fn test_try_methods() {
+        let mut range_vec = RangeVec::<u8>::new();
+        let small = RangeVec::<u8, u8>::new();
+        assert_eq!(small.iter(..=u8::MAX).count(), 256);
+        assert_eq!(small.iter(..).count(), 256);
+        assert_eq!(
+            small
+                .iter((Bound::Excluded(u8::MAX), Bound::Unbounded))
+                .count(),
+            0
+        );
+
+        range_vec
+            .try_mutate_many(3..6, |i, v| *v = i as u8)
+            .unwrap();
+        assert_eq!(range_vec.range(), Some(3..=5));
+        assert!(matches!(
+            range_vec.try_mutate_many(usize::MAX - 1.., |_, v| *v = 1),
+            Err(RangeVecError::TryReserve(_))
+        ));
+        assert_eq!(range_vec.range(), Some(3..=5));
+
+        // The stored range may end at `I::MAX`
+        let mut edge = RangeVec::<u8, u8>::new();
+        assert_eq!(edge.try_mutate_many(250.., |_, v| *v = 1), Ok(()));
+        assert_eq!(edge.range(), Some(250..=u8::MAX));
+        assert_eq!(
+            edge.try_as_mut_slices_with(.., |left, right| left.len() + right.len()),
+            Ok(256)
+        );
+
+        assert!(matches!(
+            range_vec.try_make_contiguous_with(..usize::MAX, |slice| slice.len()),
+            Err(RangeVecError::TryReserve(_))
+        ));
+        assert_eq!(range_vec.range(), Some(3..=5));
+        assert_eq!(
+            range_vec.try_make_contiguous_with(4..8, |slice| slice.to_vec()),
+            Ok(vec![4, 5, 0, 0])
+        );
+        assert_eq!(range_vec.range(), Some(3..=5));
+    }
+
+    #[test]
+    fn test_try_set_get_mut_with() {
+        let mut range_vec = RangeVec::<u32, u64>::new();
+        assert_eq!(range_vec.try_set(10, 1), Ok(()));
+        assert_eq!(range_vec.try_get_mut_with(12, |v| *v = 2), Ok(()));
+        assert_eq!(range_vec.range(), Some(10..=12));
+
+        // Default values at unallocatable indices are fine
+        assert_eq!(range_vec.try_set(u64::MAX, 0), Ok(()));
+        assert_eq!(range_vec.try_get_mut_with(u64::MAX - 1, |v| *v), Ok(0));
+
+        assert!(range_vec.try_set(u64::MAX, 3).is_err());
+        assert!(matches!(
+            range_vec.try_get_mut_with(u64::MAX - 1, |v| *v = 3),
+            Err(RangeVecError::TryReserve(_))
+        ));
+        assert_eq!(range_vec.range(), Some(10..=12));
+
+        // Reserving capacity doesn't change the stored range
+        assert_eq!(range_vec.try_grow_to_include(100), Ok(()));
+        assert_eq!(range_vec.range(), Some(10..=12));
+        assert!(range_vec.try_grow_to_include(u64::MAX).is_err());
+        assert_eq!(
+            RangeVec::<u8, i128>::new().try_grow_to_include(i128::MAX),
+            Ok(())
+        );
+        let mut wide = RangeVec::<u8, i128>::new();
+        wide.set(0, 1);
+        assert_eq!(
+            wide.try_grow_to_include(i128::MAX),
+            Err(RangeVecError::CapacityOverflow)
+        );
+
+        assert_eq!(range_vec.try_set(10, 0), Ok(()));
+        assert_eq!(range_vec.range(), Some(12..=12));
+    }
+
+    #[test]
+    fn test_set_max_index() {
+        let mut range_vec = RangeVec::<u8, u16>::new();
+        range_vec.set(0xFFFF, 1);
+        range_vec.set(0xFFFD, 2);
+        assert_eq!(range_vec.range(), Some(0xFFFD..=0xFFFF));
+        assert_eq!(range_vec[0xFFFF], 1);
+        assert_eq!(
+            range_vec.iter(0xFFFD..).copied().collect::<Vec<_>>(),
+            vec![2, 0, 1]
+        );
+        assert_eq!(range_vec.stored().next_back(), Some((0xFFFF, &1)));
+        assert_eq!(
+            range_vec.runs(0xFFF0..).collect::<Vec<_>>(),
+            vec![
+                (0xFFF0..=0xFFFC, &0),
+                (0xFFFD..=0xFFFD, &2),
+                (0xFFFE..=0xFFFE, &0),
+                (0xFFFF..=0xFFFF, &1),
+            ]
+        );
+        assert_eq!(
+            RangeVec::new()
+                .diff(&range_vec)
+                .map(|span| span.range)
+                .collect::<Vec<_>>(),
+            vec![0xFFFD..=0xFFFD, 0xFFFF..=0xFFFF]
+        );
+        range_vec.reset(0xFFFF);
+        assert_eq!(range_vec.range(), Some(0xFFFD..=0xFFFD));
+
+        let mut full = RangeVec::<u8, u8>::new();
+        full.mutate_many(.., |i, v| *v = i | 1);
+        assert_eq!(full.range(), Some(0..=u8::MAX));
+        assert_eq!(full.range_size(), 256);
+        assert_eq!(full.non_default().count(), 256);
+        full.shift_right(1);
+        assert_eq!(full.range(), Some(1..=u8::MAX));
+        assert_eq!(full[u8::MAX], 0xFF);
+    }
+
+    #[test]
+    fn test_max_span() {
+        let mut window = RangeVec::<u8>::with_max_span(4, OverflowPolicy::EvictFar);
+        window.copy_from_slice(10, &[1, 2, 3]);
+        window.set(15, 4);
+        assert_eq!(window.range(), Some(12..=15));
+        assert_eq!(
+            window.iter(12..16).copied().collect::<Vec<_>>(),
+            vec![3, 0, 0, 4]
+        );
+        // Sliding left past the whole window leaves only the new value
+        window.set(5, 5);
+        assert_eq!(window.range(), Some(5..=5));
+        window.copy_from_slice(0, &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(window.range(), Some(2..=5));
+        assert_eq!(window.range_size(), 4);
+
+        let mut pinned = RangeVec::<u8>::with_max_span(4, OverflowPolicy::Discard);
+        pinned.set_range(10, [1, 2, 3, 4, 5, 6]);
+        assert_eq!(pinned.range(), Some(10..=13));
+        pinned.mutate_many(8..12, |_, v| *v += 1);
+        assert_eq!(
+            pinned.iter(8..14).copied().collect::<Vec<_>>(),
+            vec![0, 0, 2, 3, 3, 4]
+        );
+        *pinned.get_mut(20) = 1;
+        assert_eq!(pinned.range(), Some(10..=13));
+
+        let mut strict = RangeVec::<u8, u8>::with_max_span(4, OverflowPolicy::Reject);
+        strict.set(10, 1);
+        assert_eq!(strict.try_set(13, 1), Ok(()));
+        assert_eq!(strict.try_set(14, 1), Err(RangeVecError::SpanOverflow));
+        assert_eq!(
+            strict.try_get_mut_with(9, |v| *v = 1),
+            Err(RangeVecError::SpanOverflow)
+        );
+        assert_eq!(
+            strict.try_make_contiguous_with(0..5, |slice| slice.len()),
+            Err(RangeVecError::SpanOverflow)
+        );
+        assert_eq!(strict.range(), Some(10..=13));
+        assert_eq!(strict.max_span(), Some((4, OverflowPolicy::Reject)));
+    }
+
+    #[test]
+    #[should_panic(expected = "maximum span exceeded in RangeVec")]
+    fn test_max_span_reject() {
+        let mut range_vec = RangeVec::<u8>::with_max_span(2, OverflowPolicy::Reject);
+        range_vec.set(0, 1);
+        range_vec.set(2, 1);
+    }
+
+    #[test]
+    fn test_shift() {
+        let mut range_vec = RangeVec::<u8, i8>::new();
+        range_vec.copy_from_slice(-2, &[1, 2, 3]);
+        range_vec.shift_right(100);
+        assert_eq!(range_vec.range(), Some(98..=100));
+        range_vec.shift_left(200);
+        assert_eq!(range_vec.range(), Some(-102..=-100));
+        assert_eq!(range_vec[-101], 2);
+
+        range_vec.shift_left(27);
+        assert_eq!(range_vec.range(), Some(-128..=-127));
+        assert_eq!(range_vec[-128], 2);
+        range_vec.shift_right(usize::MAX);
+        assert!(range_vec.is_empty());
+
+        // Shifting an empty RangeVec keeps it empty without moving the offset out of range
+        range_vec.shift_left(1000);
+        range_vec.set(0, 1);
+        assert_eq!(range_vec.range(), Some(0..=0));
+    }
+
+    #[test]
+    fn test_dirty_since() {
+        let mut range_vec = RangeVec::<u8, i32>::new();
+        range_vec.set(0, 1);
+        let start = range_vec.checkpoint();
+        assert_eq!(range_vec.dirty_since(start).count(), 0);
+
+        *range_vec.get_mut(5) = 1;
+        range_vec.get_mut_with(6, |v| *v = 2);
+        range_vec.mutate_many(20..22, |_, v| *v += 1);
+        let middle = range_vec.checkpoint();
+        range_vec.as_mut_slices_with(-10..-8, |left, _| left[0] = 3);
+        range_vec.iter_mut(30..31).finish();
+        assert_eq!(
+            range_vec.dirty_since(start).collect::<Vec<_>>(),
+            vec![-10..=-9, 5..=6, 20..=21, 30..=30]
+        );
+        assert_eq!(
+            range_vec.dirty_since(middle).collect::<Vec<_>>(),
+            vec![-10..=-9, 30..=30]
+        );
+
+        let end = range_vec.checkpoint();
+        range_vec.truncate(0..21);
+        assert_eq!(
+            range_vec.dirty_since(end).collect::<Vec<_>>(),
+            vec![-10..=-1, 21..=21]
+        );
+
+        let end = range_vec.checkpoint();
+        range_vec.shift_right(2);
+        assert_eq!(range_vec.dirty_since(end).collect::<Vec<_>>(), vec![0..=22]);
+
+        let end = range_vec.checkpoint();
+        let mut ones = RangeVec::<u8, i32>::with_default(1);
+        range_vec.merge_from(&ones, |a, b| *a |= b);
+        assert_eq!(
+            range_vec.dirty_since(end).collect::<Vec<_>>(),
+            vec![i32::MIN..=i32::MAX]
+        );
+        ones.set(0, 0);
+        let end = ones.checkpoint();
+        ones.reset(5);
+        ones.reset(0);
+        assert_eq!(ones.dirty_since(end).collect::<Vec<_>>(), vec![0..=0]);
+
+        range_vec.discard_dirty_before(middle);
+        assert_eq!(
+            range_vec.dirty_since(start).collect::<Vec<_>>(),
+            range_vec.dirty_since(middle).collect::<Vec<_>>()
+        );
+
+        // Writes discarded under the maximum span are not recorded
+        let mut pinned = RangeVec::<u8>::with_max_span(4, OverflowPolicy::Discard);
+        pinned.set(0, 1);
+        let start = pinned.checkpoint();
+        pinned.set(100, 2);
+        *pinned.get_mut(101) = 2;
+        pinned.get_mut_with(102, |v| *v = 2);
+        assert_eq!(pinned.dirty_since(start).count(), 0);
+        pinned.copy_from_slice(2, &[3, 0, 4, 5]);
+        pinned.mutate_many(1..=6, |i, v| *v += (i == 6) as u8);
+        assert_eq!(pinned.range(), Some(0..=2));
+        assert_eq!(pinned.dirty_since(start).collect::<Vec<_>>(), vec![1..=5]);
+    }
+
+    #[test]
+    fn test_generations() {
+        let mut range_vec = RangeVec::<u8, i32>::new();
+        range_vec.set(10, 1);
+        assert_eq!(range_vec.generation(), None);
+        assert_eq!(range_vec.next_generation(), 1);
+        assert_eq!(range_vec.modified_at(10), None);
+
+        // The fallible methods reserve room for the stamps too
+        range_vec.try_set(12, 2).unwrap();
+        range_vec.try_grow_to_include(20).unwrap();
+        range_vec.reset(12);
+        assert_eq!(range_vec.modified_at(12), None);
+        assert_eq!(range_vec.range(), Some(10..=10));
+
+        // Stamps follow the ring buffer as it grows in both directions
+        range_vec.set(5, 2);
+        range_vec.next_generation();
+        range_vec.set(15, 3);
+        range_vec.copy_from_slice(7, &[4, 0]);
+        assert_eq!(range_vec.range(), Some(5..=15));
+        let stamps: Vec<_> = (5..16).map(|i| range_vec.modified_at(i)).collect();
+        assert_eq!(
+            stamps,
+            vec![
+                Some(1),
+                None,
+                Some(2),
+                Some(2),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(2)
+            ]
+        );
+        assert_eq!(range_vec.modified_at(100), None);
+
+        // And as it shrinks or moves
+        range_vec.next_generation();
+        range_vec.reset(5);
+        range_vec.truncate(..15);
+        assert_eq!(range_vec.range(), Some(7..=10));
+        assert_eq!(range_vec.modified_at(7), Some(2));
+        assert_eq!(range_vec.modified_at(10), None);
+        range_vec.shift_left(2);
+        assert_eq!(range_vec.modified_at(5), Some(3));
+        assert_eq!(range_vec.modified_at(8), Some(3));
+
+        range_vec.next_generation();
+        *range_vec.get_mut(8) += 1;
+        range_vec.as_mut_slices_with(3..4, |left, _| left[0] = 5);
+        let modified: Vec<_> = range_vec.iter_modified_after(3).collect();
+        assert_eq!(modified, vec![(3, &5), (8, &2)]);
+        assert_eq!(range_vec.iter_modified_after(4).count(), 0);
+
+        range_vec.clear();
+        range_vec.set(0, 1);
+        assert_eq!(range_vec.modified_at(0), Some(4));
+    }
 }
@@ -0,0 +1,149 @@
+use std::ops::{
+    Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Sub, SubAssign,
+};
+
+#[cfg(test)]
+use crate::OverflowPolicy;
+use crate::{RangeIndex, RangeVec};
+
+/// Implements an element-wise binary operator for `RangeVec`, in terms of
+/// [`zip_with`](RangeVec::zip_with) and [`merge_from`](RangeVec::merge_from).
+macro_rules! impl_binary_op {
+    ($Op:ident, $op:ident, $OpAssign:ident, $op_assign:ident) => {
+        /// The result is a new `RangeVec` without a maximum span.
+        impl<T, I> $Op<&RangeVec<T, I>> for &RangeVec<T, I>
+        where
+            T: $Op<Output = T> + Eq + Clone,
+            I: RangeIndex,
+        {
+            type Output = RangeVec<T, I>;
+
+            fn $op(self, rhs: &RangeVec<T, I>) -> Self::Output {
+                self.zip_with(rhs, |a, b| a.clone().$op(b.clone()))
+            }
+        }
+
+        /// Combines in place with [`merge_from`](RangeVec::merge_from), so under a maximum span of
+        /// `self` each combined value follows its [`OverflowPolicy`](crate::OverflowPolicy), and
+        /// this panics if a value would be written past it under `OverflowPolicy::Reject`.
+        impl<T, I> $Op<&RangeVec<T, I>> for RangeVec<T, I>
+        where
+            T: $Op<Output = T> + Eq + Clone,
+            I: RangeIndex,
+        {
+            type Output = RangeVec<T, I>;
+
+            fn $op(mut self, rhs: &RangeVec<T, I>) -> Self::Output {
+                self.merge_from(rhs, |a, b| *a = a.clone().$op(b.clone()));
+                self
+            }
+        }
+
+        /// Combines in place with [`merge_from`](RangeVec::merge_from), so under a maximum span of
+        /// `self` each combined value follows its [`OverflowPolicy`](crate::OverflowPolicy), and
+        /// this panics if a value would be written past it under `OverflowPolicy::Reject`.
+        impl<T, I> $Op for RangeVec<T, I>
+        where
+            T: $Op<Output = T> + Eq + Clone,
+            I: RangeIndex,
+        {
+            type Output = RangeVec<T, I>;
+
+            fn $op(self, rhs: RangeVec<T, I>) -> Self::Output {
+                self.$op(&rhs)
+            }
+        }
+
+        /// Combines in place with [`merge_from`](RangeVec::merge_from), so under a maximum span of
+        /// `self` each combined value follows its [`OverflowPolicy`](crate::OverflowPolicy), and
+        /// this panics if a value would be written past it under `OverflowPolicy::Reject`.
+        impl<T, I> $OpAssign<&RangeVec<T, I>> for RangeVec<T, I>
+        where
+            T: $OpAssign + Eq + Clone,
+            I: RangeIndex,
+        {
+            fn $op_assign(&mut self, rhs: &RangeVec<T, I>) {
+                self.merge_from(rhs, |a, b| a.$op_assign(b.clone()));
+            }
+        }
+
+        /// Combines in place with [`merge_from`](RangeVec::merge_from), so under a maximum span of
+        /// `self` each combined value follows its [`OverflowPolicy`](crate::OverflowPolicy), and
+        /// this panics if a value would be written past it under `OverflowPolicy::Reject`.
+        impl<T, I> $OpAssign for RangeVec<T, I>
+        where
+            T: $OpAssign + Eq + Clone,
+            I: RangeIndex,
+        {
+            fn $op_assign(&mut self, rhs: RangeVec<T, I>) {
+                self.$op_assign(&rhs);
+            }
+        }
+    };
+}
+
+impl_binary_op!(Add, add, AddAssign, add_assign);
+impl_binary_op!(Sub, sub, SubAssign, sub_assign);
+impl_binary_op!(BitOr, bitor, BitOrAssign, bitor_assign);
+impl_binary_op!(BitAnd, bitand, BitAndAssign, bitand_assign);
+impl_binary_op!(BitXor, bitxor, BitXorAssign, bitxor_assign);
+
+#[test]
+fn test_ops() {
+    let mut a = RangeVec::<i32>::new();
+    a.copy_from_slice(3, &[1, 2, 3]);
+    let mut b = RangeVec::<i32>::new();
+    b.copy_from_slice(5, &[-3, 4]);
+
+    let sum = &a + &b;
+    assert_eq!(sum.range(), Some(3..=6));
+    assert_eq!(
+        sum.iter(3..7).copied().collect::<Vec<_>>(),
+        vec![1, 2, 0, 4]
+    );
+    assert_eq!(a.clone() + b.clone(), sum);
+    assert_eq!(&(&sum - &b), &a);
+
+    let mut c = a.clone();
+    c -= &a;
+    assert!(c.is_empty());
+    c += b.clone();
+    assert_eq!(c, b);
+
+    let mut flags = RangeVec::<u8, i64>::new();
+    flags.copy_from_slice(-2, &[0b01, 0b11]);
+    let mut mask = RangeVec::<u8, i64>::new();
+    mask.set(-1, 0b10);
+    mask.set(4, 0b10);
+    assert_eq!((&flags & &mask).range(), Some(-1..=-1));
+    assert_eq!((&flags | &mask).range(), Some(-2..=4));
+    flags ^= &mask;
+    assert_eq!(
+        flags.iter(-2..0).copied().collect::<Vec<_>>(),
+        vec![0b01, 0b01]
+    );
+    assert_eq!(flags[4], 0b10);
+}
+
+#[test]
+fn test_ops_max_span() {
+    let mut window = RangeVec::<u32>::with_max_span(4, OverflowPolicy::EvictFar);
+    window.copy_from_slice(0, &[1, 2]);
+    let mut hits = RangeVec::<u32>::new();
+    hits.set(1, 1);
+    hits.set(8, 1);
+
+    let sum = &window + &hits;
+    assert_eq!(sum.range(), Some(0..=8));
+    assert_eq!(sum.max_span(), None);
+
+    window += &hits;
+    assert_eq!(window.range(), Some(8..=8));
+    assert_eq!(window[8], 1);
+
+    let mut pinned = RangeVec::<u32>::with_max_span(4, OverflowPolicy::Discard);
+    pinned.copy_from_slice(0, &[1, 2]);
+    let pinned = pinned + hits;
+    assert_eq!(pinned.range(), Some(0..=1));
+    assert_eq!(pinned.iter(0..2).copied().collect::<Vec<_>>(), vec![1, 3]);
+}
@@ -0,0 +1,298 @@
+use std::ops::{Bound, Index, Range};
+
+use crate::{
+    index::{add, offset_from, sub},
+    Iter, RangeIndex, RangeVec,
+};
+
+/// `ScrollingRangeVec` is a [`RangeVec`] which tracks a viewport, for use as the backing storage
+/// of a scrollback buffer or any other scrolling data.
+///
+/// Only the values inside of the viewport, or within `margin` indices of either side of it, are
+/// retained. Whenever the viewport is scrolled with [`scroll_by`] or [`scroll_to`], every value
+/// outside of the new retained range is reset to the default value. Since this is done with
+/// [`RangeVec::truncate`], the cost of scrolling depends only on the number of evicted values,
+/// not on the size of the stored range.
+///
+/// # Examples
+///
+/// ```
+/// # use range_vec::ScrollingRangeVec;
+/// let mut lines: ScrollingRangeVec<u32> = ScrollingRangeVec::new(0..4, 2);
+/// for i in 0..6 {
+///     lines.set(i, i as u32 + 1);
+/// }
+/// assert_eq!(lines.range_vec().range(), Some(0..=5));
+///
+/// lines.scroll_by(3);
+/// assert_eq!(lines.viewport(), 3..7);
+/// assert_eq!(lines.range_vec().range(), Some(1..=5));
+/// assert_eq!(lines[0], 0);
+/// assert_eq!(lines[5], 6);
+/// ```
+///
+/// [`scroll_by`]: ScrollingRangeVec::scroll_by
+/// [`scroll_to`]: ScrollingRangeVec::scroll_to
+#[derive(Debug, Clone)]
+pub struct ScrollingRangeVec<T, I = usize> {
+    range_vec: RangeVec<T, I>,
+    viewport: Range<I>,
+    margin: usize,
+}
+
+impl<T, I> Index<I> for ScrollingRangeVec<T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    type Output = T;
+
+    fn index(&self, index: I) -> &Self::Output {
+        self.range_vec.get(index)
+    }
+}
+
+impl<T, I> ScrollingRangeVec<T, I>
+where
+    I: RangeIndex,
+{
+    /// Creates an empty `ScrollingRangeVec` where every index holds `default`, with the given
+    /// viewport, retaining values up to `margin` indices away from it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::ScrollingRangeVec;
+    /// let range_vec: ScrollingRangeVec<u8> = ScrollingRangeVec::with_default(b' ', 0..80, 0);
+    /// assert_eq!(range_vec[5], b' ');
+    /// ```
+    pub fn with_default(default: T, viewport: Range<I>, margin: usize) -> Self {
+        Self {
+            range_vec: RangeVec::with_default(default),
+            viewport,
+            margin,
+        }
+    }
+
+    /// Returns the current viewport.
+    pub fn viewport(&self) -> Range<I> {
+        self.viewport.clone()
+    }
+
+    /// Returns the number of indices on either side of the viewport whose values are retained
+    /// when scrolling.
+    pub fn margin(&self) -> usize {
+        self.margin
+    }
+
+    /// Returns the bounds of the indices whose values are retained when scrolling: the viewport,
+    /// extended by the margin on both sides and clamped to `I::MIN..=I::MAX`. The end is unbounded
+    /// if extending it would pass `I::MAX`, so that `I::MAX` itself is retained.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::ops::Bound;
+    /// # use range_vec::ScrollingRangeVec;
+    /// let range_vec: ScrollingRangeVec<u8> = ScrollingRangeVec::new(1..5, 2);
+    /// assert_eq!(
+    ///     range_vec.retained_range(),
+    ///     (Bound::Included(0), Bound::Excluded(7)),
+    /// );
+    /// ```
+    pub fn retained_range(&self) -> (Bound<I>, Bound<I>) {
+        let start = self
+            .viewport
+            .start
+            .checked_sub_usize(self.margin)
+            .unwrap_or(I::MIN);
+        let end = self
+            .viewport
+            .end
+            .checked_add_usize(self.margin)
+            .map_or(Bound::Unbounded, Bound::Excluded);
+        (Bound::Included(start), end)
+    }
+
+    /// Returns a reference to the underlying [`RangeVec`].
+    pub fn range_vec(&self) -> &RangeVec<T, I> {
+        &self.range_vec
+    }
+
+    /// Consumes the `ScrollingRangeVec`, returning the underlying [`RangeVec`].
+    pub fn into_inner(self) -> RangeVec<T, I> {
+        self.range_vec
+    }
+}
+
+impl<T, I> ScrollingRangeVec<T, I>
+where
+    T: Default,
+    I: RangeIndex,
+{
+    /// Creates an empty (all-default) `ScrollingRangeVec` with the given viewport, retaining
+    /// values up to `margin` indices away from it.
+    pub fn new(viewport: Range<I>, margin: usize) -> Self {
+        Self {
+            range_vec: RangeVec::new(),
+            viewport,
+            margin,
+        }
+    }
+}
+
+impl<T, I> ScrollingRangeVec<T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    /// Provides a reference to the element at the given index, or to a default element.
+    pub fn get(&self, index: I) -> &T {
+        self.range_vec.get(index)
+    }
+
+    /// Set the value at index `index`. Values may be written anywhere, but values outside of the
+    /// [`retained_range`](ScrollingRangeVec::retained_range) are evicted on the next scroll.
+    pub fn set(&mut self, index: I, value: T) {
+        self.range_vec.set(index, value);
+    }
+
+    /// Mutably access the underlying [`RangeVec`]. Any value returned from `f` will be returned
+    /// from the method. As with [`set`](ScrollingRangeVec::set), values written outside of the
+    /// retained range are evicted on the next scroll.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::ScrollingRangeVec;
+    /// let mut range_vec: ScrollingRangeVec<u8> = ScrollingRangeVec::new(10..20, 0);
+    /// range_vec.range_vec_mut_with(|range_vec| range_vec.copy_from_slice(12, b"hello"));
+    /// assert_eq!(range_vec[14], b'l');
+    /// ```
+    pub fn range_vec_mut_with<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut RangeVec<T, I>) -> R,
+    {
+        f(&mut self.range_vec)
+    }
+
+    /// Creates an iterator over the values inside of the viewport.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::ScrollingRangeVec;
+    /// let mut range_vec: ScrollingRangeVec<i32> = ScrollingRangeVec::new(2..5, 1);
+    /// range_vec.set(3, 1);
+    /// assert_eq!(range_vec.iter_viewport().copied().collect::<Vec<i32>>(), vec![0, 1, 0]);
+    /// ```
+    pub fn iter_viewport(&self) -> Iter<'_, T, I> {
+        self.range_vec.iter(self.viewport.clone())
+    }
+
+    /// Move the viewport by `delta` indices, to the right if it is positive or to the left if it
+    /// is negative, and evict every value outside of the new retained range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new viewport would start or end outside of `I::MIN..=I::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::ScrollingRangeVec;
+    /// let mut range_vec: ScrollingRangeVec<i32, i64> = ScrollingRangeVec::new(0..10, 0);
+    /// range_vec.set(0, 1);
+    /// range_vec.scroll_by(-5);
+    /// assert_eq!(range_vec.viewport(), -5..5);
+    /// assert_eq!(range_vec[0], 1);
+    ///
+    /// range_vec.scroll_by(-5);
+    /// assert_eq!(range_vec[0], 0);
+    /// ```
+    pub fn scroll_by(&mut self, delta: isize) {
+        let distance = delta.unsigned_abs();
+        self.viewport = if delta >= 0 {
+            add(self.viewport.start, distance)..add(self.viewport.end, distance)
+        } else {
+            sub(self.viewport.start, distance)..sub(self.viewport.end, distance)
+        };
+        self.evict();
+    }
+
+    /// Move the viewport to start at `start`, keeping its length, and evict every value outside of
+    /// the new retained range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new viewport would end past `I::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::ScrollingRangeVec;
+    /// let mut range_vec: ScrollingRangeVec<i32> = ScrollingRangeVec::new(0..10, 5);
+    /// range_vec.set(3, 1);
+    /// range_vec.set(30, 2);
+    /// range_vec.scroll_to(20);
+    /// assert_eq!(range_vec.viewport(), 20..30);
+    /// assert_eq!(range_vec.range_vec().range(), Some(30..=30));
+    /// ```
+    pub fn scroll_to(&mut self, start: I) {
+        let len = if self.viewport.is_empty() {
+            0
+        } else {
+            offset_from(self.viewport.end, self.viewport.start)
+        };
+        self.viewport = start..add(start, len);
+        self.evict();
+    }
+
+    /// Resets every value outside of the retained range to the default value.
+    fn evict(&mut self) {
+        let retained = self.retained_range();
+        self.range_vec.truncate(retained);
+    }
+}
+
+#[test]
+fn test_scrolling_range_vec() {
+    let mut range_vec = ScrollingRangeVec::<u8, u8>::new(10..20, 3);
+    assert_eq!(
+        range_vec.retained_range(),
+        (Bound::Included(7), Bound::Excluded(23))
+    );
+    for i in 0..30 {
+        range_vec.set(i, i);
+    }
+    range_vec.scroll_by(0);
+    assert_eq!(range_vec.range_vec().range(), Some(7..=22));
+
+    range_vec.scroll_by(-2);
+    assert_eq!(range_vec.viewport(), 8..18);
+    assert_eq!(range_vec.range_vec().range(), Some(7..=20));
+    assert_eq!(range_vec[5], 0);
+    assert_eq!(range_vec[7], 7);
+
+    // The retained range is clamped at the edges of the index type
+    range_vec.scroll_to(0);
+    assert_eq!(
+        range_vec.retained_range(),
+        (Bound::Included(0), Bound::Excluded(13))
+    );
+    assert_eq!(range_vec.range_vec().range(), Some(7..=12));
+
+    range_vec.scroll_to(u8::MAX - 10);
+    assert_eq!(range_vec.viewport(), 245..255);
+    assert_eq!(
+        range_vec.retained_range(),
+        (Bound::Included(242), Bound::Unbounded)
+    );
+    assert!(range_vec.range_vec().is_empty());
+    assert_eq!(range_vec.iter_viewport().count(), 10);
+
+    // `I::MAX` itself is retained
+    range_vec.set(u8::MAX, 1);
+    range_vec.scroll_by(0);
+    assert_eq!(range_vec[u8::MAX], 1);
+}
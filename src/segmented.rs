@@ -0,0 +1,601 @@
+use std::{
+    iter::{self, FusedIterator},
+    ops::{Index, RangeBounds, RangeInclusive},
+};
+
+use crate::{
+    index::{add, offset_from, sub},
+    indices, range_bounds_to_range, RangeIndex, RangeVec,
+};
+
+/// `SegmentedRangeVec` is a variant of [`RangeVec`] which stores its non-default values in
+/// several separate segments, rather than in a single contiguous ring buffer. This allows it to
+/// efficiently store clusters of non-default values that are very far apart, such as the stack
+/// and the heap of an emulated program, without filling the space between them with default
+/// values.
+///
+/// Each segment is itself a normalized [`RangeVec`], and the segments are kept sorted by index.
+/// Two segments are merged whenever the gap of default values between them is at most `max_gap`,
+/// and a segment is split whenever a run of more than `max_gap` default values appears inside of
+/// it. Because of this, the segments depend only on the stored values and `max_gap`, not on the
+/// order of the mutations that produced them.
+///
+/// Like [`RangeVec`], `SegmentedRangeVec` requires that the stored type implement [`Eq`] and
+/// [`Clone`], and returns a default value for any index outside of its segments. Accessing an
+/// index requires a binary search over the segments, so a `RangeVec` should be preferred if the
+/// non-default values are expected to be in a single small range.
+///
+/// # Examples
+///
+/// ```
+/// # use range_vec::SegmentedRangeVec;
+/// let mut range_vec: SegmentedRangeVec<u8, u64> = SegmentedRangeVec::new(16);
+/// range_vec.set(0x1000, 1);
+/// range_vec.set(0x1004, 2);
+/// range_vec.set(0x7fff_0000, 3);
+/// assert_eq!(range_vec.segments().len(), 2);
+/// assert_eq!(range_vec.range(), Some(0x1000..=0x7fff_0000));
+/// assert_eq!(range_vec[0x1004], 2);
+/// assert_eq!(range_vec[0x2000], 0);
+/// ```
+///
+/// [`Eq`]: std::cmp::Eq
+/// [`Clone`]: std::clone::Clone
+#[derive(Debug, Clone)]
+pub struct SegmentedRangeVec<T, I = usize> {
+    segments: Vec<RangeVec<T, I>>,
+    max_gap: usize,
+    default_item: T,
+}
+
+impl<T, I> Index<I> for SegmentedRangeVec<T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    type Output = T;
+
+    fn index(&self, index: I) -> &Self::Output {
+        self.get(index)
+    }
+}
+
+impl<T, I> SegmentedRangeVec<T, I>
+where
+    I: RangeIndex,
+{
+    /// Creates an empty `SegmentedRangeVec` where every index holds `default`, and segments
+    /// separated by at most `max_gap` default values are merged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::SegmentedRangeVec;
+    /// let mut range_vec: SegmentedRangeVec<u8> = SegmentedRangeVec::with_default(0xFF, 4);
+    /// assert_eq!(range_vec.get(5), &0xFF);
+    ///
+    /// range_vec.set(5, 0);
+    /// range_vec.set(10, 0);
+    /// assert_eq!(range_vec.segments().len(), 1);
+    /// ```
+    pub fn with_default(default: T, max_gap: usize) -> Self {
+        Self {
+            segments: Vec::new(),
+            max_gap,
+            default_item: default,
+        }
+    }
+
+    /// Returns a reference to the default value, which is returned for any index outside of the
+    /// stored segments.
+    pub fn default_value(&self) -> &T {
+        &self.default_item
+    }
+
+    /// Returns the largest number of consecutive default values that may be stored inside of a
+    /// segment. Segments separated by at most this many default values are merged.
+    pub fn max_gap(&self) -> usize {
+        self.max_gap
+    }
+
+    /// Returns the stored segments, sorted by index. Each segment is non-empty, and separated from
+    /// the next by more than [`max_gap`](SegmentedRangeVec::max_gap) default values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::SegmentedRangeVec;
+    /// let mut range_vec: SegmentedRangeVec<i32> = SegmentedRangeVec::new(2);
+    /// range_vec.set(5, 1);
+    /// range_vec.set(8, 2);
+    /// range_vec.set(12, 3);
+    /// let ranges: Vec<_> = range_vec
+    ///     .segments()
+    ///     .iter()
+    ///     .filter_map(|segment| segment.range())
+    ///     .collect();
+    /// assert_eq!(ranges, vec![5..=8, 12..=12]);
+    /// ```
+    pub fn segments(&self) -> &[RangeVec<T, I>] {
+        &self.segments
+    }
+
+    /// Returns the range spanning all of the stored segments, from the leftmost to the rightmost
+    /// non-default value, both inclusive. This will return `None` if there are no non-default
+    /// values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::SegmentedRangeVec;
+    /// let mut range_vec: SegmentedRangeVec<i32> = SegmentedRangeVec::new(2);
+    /// assert_eq!(range_vec.range(), None);
+    ///
+    /// range_vec.set(5, 1);
+    /// range_vec.set(100, 2);
+    /// assert_eq!(range_vec.range(), Some(5..=100));
+    /// ```
+    pub fn range(&self) -> Option<RangeInclusive<I>> {
+        let start = self.segments.first()?.offset;
+        let end = segment_last(self.segments.last()?);
+        Some(start..=end)
+    }
+
+    /// Returns `true` if there are any stored values, i.e., if any values are non-default.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Creates an iterator over the specified range. A range unbounded on the left will start at
+    /// `I::MIN`, and one unbounded on the right will end at `I::MAX`, both inclusive. The iterator
+    /// will emit values of type `&T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::SegmentedRangeVec;
+    /// let mut range_vec: SegmentedRangeVec<i32> = SegmentedRangeVec::new(0);
+    /// range_vec.set(3, 1);
+    /// range_vec.set(5, 2);
+    /// let numbers: Vec<i32> = range_vec.iter(1..=7).copied().collect();
+    /// assert_eq!(numbers, vec![0, 0, 1, 0, 2, 0, 0]);
+    /// ```
+    pub fn iter(&self, range: impl RangeBounds<I>) -> SegmentedIter<'_, T, I> {
+        SegmentedIter::new(self, range_bounds_to_range(range))
+    }
+
+    /// Clears the `SegmentedRangeVec`, resetting all values to default.
+    pub fn clear(&mut self) {
+        self.segments.clear();
+    }
+
+    /// Returns the number of segments starting at or before `index`. The segment which may
+    /// contain `index` is the one before this position.
+    fn segment_position(&self, index: I) -> usize {
+        self.segments
+            .partition_point(|segment| segment.offset <= index)
+    }
+}
+
+impl<T, I> SegmentedRangeVec<T, I>
+where
+    T: Default,
+    I: RangeIndex,
+{
+    /// Creates an empty (all-default) `SegmentedRangeVec`, where segments separated by at most
+    /// `max_gap` default values are merged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::SegmentedRangeVec;
+    /// let range_vec: SegmentedRangeVec<i32> = SegmentedRangeVec::new(64);
+    /// assert_eq!(range_vec.range(), None);
+    /// ```
+    pub fn new(max_gap: usize) -> Self {
+        Self::with_default(T::default(), max_gap)
+    }
+}
+
+impl<T, I> SegmentedRangeVec<T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    /// Provides a reference to the element at the given index, or to a default element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::SegmentedRangeVec;
+    /// let mut range_vec: SegmentedRangeVec<i32> = SegmentedRangeVec::new(8);
+    /// range_vec.set(5, 1);
+    /// assert_eq!(range_vec.get(5), &1);
+    /// assert_eq!(range_vec.get(10), &0);
+    /// ```
+    pub fn get(&self, index: I) -> &T {
+        match self.segment_position(index).checked_sub(1) {
+            Some(position) => self.segments[position].get(index),
+            None => &self.default_item,
+        }
+    }
+
+    /// Set the value at index `index`. If the element is not equal to the default value, it will
+    /// be added to the nearest segment within `max_gap` of it, or to a new segment if there is
+    /// none, and segments will be merged as appropriate. If it is equal to the default value, the
+    /// segment containing it will be shrunk or split as appropriate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::SegmentedRangeVec;
+    /// let mut range_vec: SegmentedRangeVec<i32> = SegmentedRangeVec::new(2);
+    /// range_vec.set(5, 1);
+    /// range_vec.set(9, 2);
+    /// assert_eq!(range_vec.segments().len(), 2);
+    ///
+    /// range_vec.set(7, 3);
+    /// assert_eq!(range_vec.segments().len(), 1);
+    ///
+    /// range_vec.set(7, 0);
+    /// assert_eq!(range_vec.segments().len(), 2);
+    /// ```
+    pub fn set(&mut self, index: I, value: T) {
+        let position = self.segment_position(index);
+
+        if value == self.default_item {
+            // Only a segment containing `index` can be affected
+            if let Some(previous) = position.checked_sub(1) {
+                if self.segment_contains(previous, index) {
+                    self.segments[previous].set(index, value);
+                    self.split_at(previous, index);
+                }
+            }
+            return;
+        }
+
+        if let Some(previous) = position.checked_sub(1).filter(|&previous| {
+            let last = segment_last(&self.segments[previous]);
+            index <= last
+                || index
+                    .checked_offset_from(last)
+                    .is_some_and(|distance| distance - 1 <= self.max_gap)
+        }) {
+            // Inside of or near the end of the previous segment: this may close the gap to the
+            // next segment
+            self.segments[previous].set(index, value);
+            self.merge_with_next(previous);
+        } else if self.segments.get(position).is_some_and(|next| {
+            next.offset
+                .checked_offset_from(add(index, 1))
+                .is_some_and(|gap| gap <= self.max_gap)
+        }) {
+            // Near the start of the next segment: the gap to the previous segment is already
+            // known to be larger than `max_gap`
+            self.segments[position].set(index, value);
+        } else {
+            let mut segment = RangeVec::with_default(self.default_item.clone());
+            segment.set(index, value);
+            self.segments.insert(position, segment);
+        }
+    }
+
+    /// Mutate the value at index `index`. The segments will be adjusted after mutation in the same
+    /// way as for [`set`](SegmentedRangeVec::set). Any value returned from the passed closure `f`
+    /// will be returned from the method.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::SegmentedRangeVec;
+    /// let mut range_vec: SegmentedRangeVec<i32> = SegmentedRangeVec::new(2);
+    /// range_vec.get_mut_with(5, |v| *v = 1);
+    /// range_vec.get_mut_with(5, |v| *v += 2);
+    /// assert_eq!(range_vec[5], 3);
+    /// ```
+    pub fn get_mut_with<F, R>(&mut self, index: I, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut value = self.get(index).clone();
+        let ret = f(&mut value);
+        self.set(index, value);
+        ret
+    }
+
+    /// Mutate a range of values. This method is equivalent to calling
+    /// [`get_mut_with`](SegmentedRangeVec::get_mut_with) repeatedly on an entire range of values,
+    /// except it does not pass through the closure's return value. The closure is also passed the
+    /// index as its first argument.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use range_vec::SegmentedRangeVec;
+    /// let mut range_vec: SegmentedRangeVec<i32> = SegmentedRangeVec::new(0);
+    /// range_vec.mutate_many(5..15, |_, v| *v += 1);
+    /// range_vec.mutate_many(8..12, |_, v| *v -= 1);
+    /// assert_eq!(range_vec.segments().len(), 2);
+    /// assert_eq!(
+    ///     range_vec.iter(5..15).copied().collect::<Vec<i32>>(),
+    ///     vec![1, 1, 1, 0, 0, 0, 0, 1, 1, 1],
+    /// );
+    /// ```
+    pub fn mutate_many<F>(&mut self, range: impl RangeBounds<I>, mut f: F)
+    where
+        F: FnMut(I, &mut T),
+    {
+        for i in indices(range_bounds_to_range(range)) {
+            let mut value = self.get(i).clone();
+            f(i, &mut value);
+            if &value != self.get(i) {
+                self.set(i, value);
+            }
+        }
+    }
+
+    /// Reset the value at a given index to the default value, and shrink or split the segment
+    /// containing it accordingly. If `index` is outside of all segments, this method is a no-op.
+    pub fn reset(&mut self, index: I) {
+        self.set(index, self.default_item.clone());
+    }
+
+    fn segment_contains(&self, position: usize, index: I) -> bool {
+        let segment = &self.segments[position];
+        index >= segment.offset && index <= segment_last(segment)
+    }
+
+    /// Merges the segment at `position` with the next one, if the gap between them is at most
+    /// `max_gap`.
+    fn merge_with_next(&mut self, position: usize) {
+        let Some(next) = self.segments.get(position + 1) else {
+            return;
+        };
+        let Some(gap) = next
+            .offset
+            .checked_offset_from(segment_last(&self.segments[position]))
+            .map(|distance| distance - 1)
+            .filter(|&gap| gap <= self.max_gap)
+        else {
+            return;
+        };
+
+        let next = self.segments.remove(position + 1);
+        let segment = &mut self.segments[position];
+        segment.data.reserve(gap + next.data.len());
+        segment
+            .data
+            .extend(iter::repeat_n(self.default_item.clone(), gap));
+        segment.data.extend(next.data);
+    }
+
+    /// Removes the segment at `position` if it has become empty, or splits it around `index` if
+    /// the run of default values containing `index` is longer than `max_gap`.
+    fn split_at(&mut self, position: usize, index: I) {
+        let segment = &mut self.segments[position];
+        if segment.is_empty() {
+            self.segments.remove(position);
+            return;
+        }
+        let Some(split) = index
+            .checked_offset_from(segment.offset)
+            .filter(|&split| split < segment.data.len())
+        else {
+            // `index` was at the edge of the segment, which has already been shrunk
+            return;
+        };
+
+        let is_default = |item: &T| item == &self.default_item;
+        let run_start = segment
+            .data
+            .range(..split)
+            .rposition(|item| !is_default(item))
+            .map_or(0, |i| i + 1);
+        let run_end = segment
+            .data
+            .range(split..)
+            .position(|item| !is_default(item))
+            .map_or(segment.data.len(), |i| split + i);
+        if run_end - run_start <= self.max_gap {
+            return;
+        }
+
+        let right = RangeVec::from_parts(
+            segment.data.split_off(run_end),
+            add(segment.offset, run_end),
+            self.default_item.clone(),
+            None,
+        );
+        segment.data.truncate(run_start);
+        self.segments.insert(position + 1, right);
+    }
+}
+
+/// An iterator over the elements of a [`SegmentedRangeVec`].
+///
+/// This `struct` is created by the [`iter`](SegmentedRangeVec::iter) method on
+/// [`SegmentedRangeVec`]. See its documentation for more.
+pub struct SegmentedIter<'a, T: 'a, I = usize> {
+    // `None` once every index has been emitted
+    iter_range: Option<RangeInclusive<I>>,
+    segments: &'a [RangeVec<T, I>],
+    default_item: &'a T,
+}
+
+impl<'a, T, I> SegmentedIter<'a, T, I>
+where
+    I: RangeIndex,
+{
+    fn new(range_vec: &'a SegmentedRangeVec<T, I>, range: Option<RangeInclusive<I>>) -> Self {
+        // Only the segments overlapping the iterated range are kept
+        let segments = match &range {
+            Some(range) => {
+                let start = range_vec
+                    .segments
+                    .partition_point(|segment| segment_last(segment) < *range.start());
+                let end = range_vec
+                    .segments
+                    .partition_point(|segment| segment.offset <= *range.end())
+                    .max(start);
+                &range_vec.segments[start..end]
+            }
+            None => &[],
+        };
+        Self {
+            iter_range: range,
+            segments,
+            default_item: &range_vec.default_item,
+        }
+    }
+}
+
+impl<'a, T, I> Iterator for SegmentedIter<'a, T, I>
+where
+    I: RangeIndex,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, end) = self.iter_range.clone()?.into_inner();
+        self.iter_range = (index < end).then(|| add(index, 1)..=end);
+
+        match self.segments.first() {
+            Some(segment) if index >= segment.offset => {
+                let position = offset_from(index, segment.offset);
+                let item = &segment.data[position];
+                if position + 1 == segment.data.len() {
+                    self.segments = &self.segments[1..];
+                }
+                Some(item)
+            }
+            _ => Some(self.default_item),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let Some(iter_range) = &self.iter_range else {
+            return (0, Some(0));
+        };
+        match iter_range
+            .end()
+            .checked_offset_from(*iter_range.start())
+            .and_then(|distance| distance.checked_add(1))
+        {
+            Some(size) => (size, Some(size)),
+            None => (usize::MAX, None),
+        }
+    }
+}
+
+impl<'a, T, I> DoubleEndedIterator for SegmentedIter<'a, T, I>
+where
+    I: RangeIndex,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (start, index) = self.iter_range.clone()?.into_inner();
+        self.iter_range = (start < index).then(|| start..=sub(index, 1));
+
+        match self.segments.last() {
+            Some(segment) if index >= segment.offset && index <= segment_last(segment) => {
+                let position = offset_from(index, segment.offset);
+                let item = &segment.data[position];
+                if position == 0 {
+                    self.segments = &self.segments[..self.segments.len() - 1];
+                }
+                Some(item)
+            }
+            _ => Some(self.default_item),
+        }
+    }
+}
+
+impl<'a, T, I> FusedIterator for SegmentedIter<'a, T, I> where I: RangeIndex {}
+
+/// Returns the index of the last value stored in `segment`, which must not be empty.
+fn segment_last<T, I: RangeIndex>(segment: &RangeVec<T, I>) -> I {
+    add(segment.offset, segment.data.len() - 1)
+}
+
+#[test]
+fn test_segmented() {
+    let mut range_vec = SegmentedRangeVec::<u8, i64>::new(2);
+    range_vec.set(10, 1);
+    range_vec.set(-10, 2);
+    range_vec.set(14, 3);
+    assert_eq!(range_vec.segments().len(), 3);
+
+    // Closing the gaps merges segments
+    range_vec.set(12, 4);
+    assert_eq!(range_vec.segments().len(), 2);
+    assert_eq!(range_vec.segments()[1].range(), Some(10..=14));
+    assert_eq!(
+        range_vec.iter(9..16).copied().collect::<Vec<_>>(),
+        vec![0, 1, 0, 4, 0, 3, 0]
+    );
+    assert_eq!(
+        range_vec.iter(9..16).rev().copied().collect::<Vec<_>>(),
+        vec![0, 3, 0, 4, 0, 1, 0]
+    );
+    range_vec.set(-7, 5);
+    assert_eq!(range_vec.segments().len(), 2);
+    range_vec.set(-6, 6);
+    assert_eq!(range_vec.segments().len(), 2);
+    assert_eq!(range_vec.segments()[0].range(), Some(-10..=-6));
+    assert_eq!(range_vec.range(), Some(-10..=14));
+
+    // Opening a long run of defaults splits segments
+    range_vec.mutate_many(-7..=10, |_, v| *v = 0);
+    assert_eq!(range_vec.segments().len(), 2);
+    assert_eq!(range_vec.segments()[0].range(), Some(-10..=-10));
+    assert_eq!(range_vec.segments()[1].range(), Some(12..=14));
+
+    let mut iter = range_vec.iter(-9..13);
+    assert_eq!(iter.size_hint(), (22, Some(22)));
+    assert_eq!(iter.next(), Some(&0));
+    assert_eq!(iter.next_back(), Some(&4));
+    assert_eq!(iter.next_back(), Some(&0));
+
+    range_vec.reset(-10);
+    range_vec.reset(12);
+    range_vec.reset(14);
+    assert!(range_vec.is_empty());
+
+    range_vec.set(i64::MAX, 1);
+    range_vec.set(i64::MAX - 3, 2);
+    assert_eq!(range_vec.segments().len(), 1);
+    assert_eq!(range_vec.range(), Some(i64::MAX - 3..=i64::MAX));
+    assert_eq!(range_vec.iter(i64::MAX - 1..).next_back(), Some(&1));
+}
+
+#[test]
+fn test_segmented_canonical() {
+    let values = [(3, 1), (20, 2), (8, 3), (12, 4), (40, 5), (5, 6)];
+    let mut forward = SegmentedRangeVec::<i32>::new(4);
+    let mut backward = SegmentedRangeVec::<i32>::new(4);
+    for &(index, value) in &values {
+        forward.set(index, value);
+    }
+    for &(index, value) in values.iter().rev() {
+        backward.set(index, value);
+    }
+
+    let segments = |range_vec: &SegmentedRangeVec<i32>| {
+        range_vec
+            .segments()
+            .iter()
+            .map(|segment| (segment.range(), segment.data.clone()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(segments(&forward), segments(&backward));
+    assert_eq!(
+        segments(&forward)
+            .into_iter()
+            .map(|(range, _)| range.unwrap())
+            .collect::<Vec<_>>(),
+        vec![3..=12, 20..=20, 40..=40]
+    );
+    assert_eq!(
+        forward.iter(..).take(50).collect::<Vec<_>>(),
+        backward.iter(..).take(50).collect::<Vec<_>>()
+    );
+}
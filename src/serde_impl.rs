@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{RangeIndex, RangeVec};
+
+#[derive(Serialize)]
+#[serde(rename = "RangeVec")]
+struct SerRangeVec<'a, T, I> {
+    offset: &'a I,
+    data: &'a VecDeque<T>,
+    default: &'a T,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "RangeVec")]
+struct DeRangeVec<T, I> {
+    offset: I,
+    data: VecDeque<T>,
+    default: T,
+}
+
+/// Only the stored range is serialized, as a struct with the fields `offset` and `data`, along
+/// with the default value in the field `default`. An empty `RangeVec` is always serialized with
+/// an offset of `I::MIN`, so that equal values serialize the same way.
+impl<T, I> Serialize for RangeVec<T, I>
+where
+    T: Serialize,
+    I: Serialize + RangeIndex,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let offset = if self.data.is_empty() {
+            I::MIN
+        } else {
+            self.offset
+        };
+        SerRangeVec {
+            offset: &offset,
+            data: &self.data,
+            default: &self.default_item,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// The default value is read from the field `default`, which is required because `T` need not
+/// implement [`Default`]. Default values at either end of
+/// the deserialized data are trimmed, so the result is normalized even if the input was not.
+impl<'de, T, I> Deserialize<'de> for RangeVec<T, I>
+where
+    T: Deserialize<'de> + Eq + Clone,
+    I: Deserialize<'de> + RangeIndex,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let DeRangeVec {
+            offset,
+            data,
+            default,
+        } = DeRangeVec::<T, I>::deserialize(deserializer)?;
+        if !data.is_empty() && offset.checked_add_usize(data.len() - 1).is_none() {
+            return Err(de::Error::custom(format_args!(
+                "RangeVec data of length {} at offset {:?} exceeds the index range",
+                data.len(),
+                offset,
+            )));
+        }
+
+        let mut range_vec = RangeVec::from_parts(data, offset, default, None);
+        range_vec.shrink_left();
+        range_vec.shrink_right();
+        Ok(range_vec)
+    }
+}
+
+#[test]
+fn test_serde() {
+    let mut range_vec = RangeVec::<u8>::new();
+    range_vec.set(5, 1);
+    range_vec.set(7, 2);
+    let json = serde_json::to_string(&range_vec).unwrap();
+    assert_eq!(json, r#"{"offset":5,"data":[1,0,2],"default":0}"#);
+
+    let range_vec: RangeVec<u8> = serde_json::from_str(&json).unwrap();
+    assert_eq!(range_vec.range(), Some(5..=7));
+    assert_eq!(range_vec[7], 2);
+
+    let range_vec: RangeVec<u8, i64> =
+        serde_json::from_str(r#"{"offset":-3,"data":[0,0,4,0,5,0],"default":0}"#).unwrap();
+    assert_eq!(range_vec.range(), Some(-1..=1));
+    assert_eq!(range_vec[-1], 4);
+
+    let range_vec: RangeVec<u8> =
+        serde_json::from_str(r#"{"offset":9,"data":[0,0],"default":0}"#).unwrap();
+    assert!(range_vec.is_empty());
+    assert_eq!(
+        serde_json::to_string(&range_vec).unwrap(),
+        serde_json::to_string(&RangeVec::<u8>::new()).unwrap()
+    );
+
+    let range_vec: RangeVec<u8, u8> =
+        serde_json::from_str(r#"{"offset":254,"data":[1,2],"default":0}"#).unwrap();
+    assert_eq!(range_vec[u8::MAX], 2);
+    assert!(serde_json::from_str::<RangeVec<u8, u8>>(
+        r#"{"offset":254,"data":[1,2,3],"default":0}"#
+    )
+    .is_err());
+
+    // The default value is required
+    assert!(serde_json::from_str::<RangeVec<u8>>(r#"{"offset":5,"data":[1]}"#).is_err());
+}
+
+#[test]
+fn test_serde_with_default() {
+    let mut range_vec = RangeVec::<u8>::with_default(0xFF);
+    range_vec.copy_from_slice(5, &[0, 7]);
+    let json = serde_json::to_string(&range_vec).unwrap();
+    assert_eq!(json, r#"{"offset":5,"data":[0,7],"default":255}"#);
+
+    let deserialized: RangeVec<u8> = serde_json::from_str(&json).unwrap();
+    assert_eq!(deserialized, range_vec);
+    assert_eq!(deserialized[4], 0xFF);
+    assert_eq!(deserialized[5], 0);
+    assert_eq!(deserialized[7], 0xFF);
+}
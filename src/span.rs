@@ -0,0 +1,19 @@
+/// What a [`RangeVec`](crate::RangeVec) created with
+/// [`with_max_span`](crate::RangeVec::with_max_span) does when a write would grow its stored range
+/// past the maximum span.
+///
+/// "Far" is relative to the written index: when writing to the right of the stored range, the far
+/// end of the stored range is its left end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    /// Reject the write. The `try_` methods return
+    /// [`RangeVecError::SpanOverflow`](crate::RangeVecError::SpanOverflow), and all other methods
+    /// panic.
+    Reject,
+    /// Evict values from the far end of the stored range to make room for the write, so that the
+    /// stored range slides to follow writes like a scrolling window.
+    EvictFar,
+    /// Discard the write, leaving the stored range untouched, so that the values written first stay
+    /// pinned in place.
+    Discard,
+}
@@ -0,0 +1,73 @@
+use std::{
+    collections::{vec_deque, TryReserveError, VecDeque},
+    iter,
+    ops::Range,
+};
+
+/// Generation stamps kept parallel to the ring buffer of a [`RangeVec`](crate::RangeVec), holding
+/// the generation each stored position was last written in, or 0 if it hasn't been written since
+/// tracking started.
+#[derive(Debug, Clone)]
+pub(crate) struct Stamps {
+    generation: u64,
+    stamps: VecDeque<u64>,
+}
+
+impl Stamps {
+    /// Starts tracking for a ring buffer of length `len`, none of whose positions are stamped.
+    pub(crate) fn new(len: usize) -> Self {
+        Self {
+            generation: 0,
+            stamps: iter::repeat_n(0, len).collect(),
+        }
+    }
+
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub(crate) fn next_generation(&mut self) -> u64 {
+        self.generation += 1;
+        self.generation
+    }
+
+    /// Returns the stamp at `position`, or `None` if it hasn't been written since tracking
+    /// started.
+    pub(crate) fn get(&self, position: usize) -> Option<u64> {
+        self.stamps
+            .get(position)
+            .copied()
+            .filter(|&stamp| stamp != 0)
+    }
+
+    pub(crate) fn iter(&self) -> vec_deque::Iter<'_, u64> {
+        self.stamps.iter()
+    }
+
+    /// Stamps every position in `positions` with the current generation.
+    pub(crate) fn stamp(&mut self, positions: Range<usize>) {
+        for stamp in self.stamps.range_mut(positions) {
+            *stamp = self.generation;
+        }
+    }
+
+    /// Adds unstamped positions on both ends, mirroring the growth of the ring buffer.
+    pub(crate) fn grow(&mut self, left: usize, right: usize) {
+        self.stamps.resize(self.stamps.len() + left + right, 0);
+        self.stamps.rotate_right(left);
+    }
+
+    /// Reserves room for `additional` more positions, so that growing by that many can't fail.
+    pub(crate) fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.stamps.try_reserve(additional)
+    }
+
+    /// Removes the stamps at `positions`, mirroring a drain of the ring buffer.
+    pub(crate) fn drain(&mut self, positions: Range<usize>) {
+        self.stamps.drain(positions);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.stamps.clear();
+    }
+}
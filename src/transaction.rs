@@ -0,0 +1,197 @@
+use std::{collections::BTreeMap, ops::Index};
+
+use crate::{index::add, RangeIndex, RangeVec};
+#[cfg(test)]
+use crate::{OverflowPolicy, RangeVecError};
+
+/// A set of staged writes to a [`RangeVec`].
+///
+/// This `struct` is passed to the closure given to the [`transaction`](RangeVec::transaction)
+/// method on [`RangeVec`]. See its documentation for more.
+///
+/// Reads through the transaction see its own staged writes on top of the values in the
+/// `RangeVec`, which is left untouched until the transaction is applied.
+pub struct Transaction<'a, T, I = usize> {
+    range_vec: &'a RangeVec<T, I>,
+    staged: BTreeMap<I, T>,
+}
+
+impl<'a, T, I> Transaction<'a, T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    pub(super) fn new(range_vec: &'a RangeVec<T, I>) -> Self {
+        Self {
+            range_vec,
+            staged: BTreeMap::new(),
+        }
+    }
+
+    pub(super) fn into_staged(self) -> BTreeMap<I, T> {
+        self.staged
+    }
+
+    /// Provides a reference to the element at the given index, including any staged write to it.
+    pub fn get(&self, index: I) -> &T {
+        self.staged
+            .get(&index)
+            .unwrap_or_else(|| self.range_vec.get(index))
+    }
+
+    /// Stage a write of `value` at index `index`.
+    pub fn set(&mut self, index: I, value: T) {
+        self.staged.insert(index, value);
+    }
+
+    /// Stage a write of consecutive values starting at index `start`, cloning them from `values`.
+    pub fn copy_from_slice(&mut self, start: I, values: &[T]) {
+        for (i, value) in values.iter().enumerate() {
+            self.set(add(start, i), value.clone());
+        }
+    }
+
+    /// Mutate the value at index `index`, staging the result. Any value returned from the passed
+    /// closure `f` will be returned from the method.
+    pub fn get_mut_with<F, R>(&mut self, index: I, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let value = self
+            .staged
+            .entry(index)
+            .or_insert_with(|| self.range_vec.get(index).clone());
+        f(value)
+    }
+
+    /// Stage a write of the default value at index `index`.
+    pub fn reset(&mut self, index: I) {
+        self.set(index, self.range_vec.default_item.clone());
+    }
+}
+
+impl<'a, T, I> Index<I> for Transaction<'a, T, I>
+where
+    T: Eq + Clone,
+    I: RangeIndex,
+{
+    type Output = T;
+
+    fn index(&self, index: I) -> &Self::Output {
+        self.get(index)
+    }
+}
+
+#[test]
+fn test_transaction() {
+    let mut range_vec = RangeVec::<u8>::new();
+    range_vec.copy_from_slice(10, &[1, 2, 3]);
+
+    let result: Result<(), &str> = range_vec.transaction(|tx| {
+        tx.set(10, 5);
+        tx.get_mut_with(11, |v| *v += 1);
+        assert_eq!(tx[10], 5);
+        Err("patch failed")
+    });
+    assert_eq!(result, Err("patch failed"));
+    assert_eq!(
+        range_vec.iter(10..13).copied().collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+
+    let result: Result<u8, ()> = range_vec.transaction(|tx| {
+        tx.reset(10);
+        tx.get_mut_with(11, |v| *v += 1);
+        tx.copy_from_slice(20, &[0, 4]);
+        tx.set(30, 0);
+        Ok(tx[11])
+    });
+    assert_eq!(result, Ok(3));
+    assert_eq!(range_vec.range(), Some(11..=21));
+    assert_eq!(range_vec[11], 3);
+    assert_eq!(range_vec[21], 4);
+}
+
+#[test]
+fn test_transaction_max_span() {
+    let stage = |tx: &mut Transaction<'_, u8>| {
+        tx.set(0, 1);
+        tx.set(10, 2);
+        Ok(())
+    };
+    for policy in [
+        OverflowPolicy::Reject,
+        OverflowPolicy::Discard,
+        OverflowPolicy::EvictFar,
+    ] {
+        // Nothing is written if the staged writes alone don't fit
+        let mut range_vec = RangeVec::<u8>::with_max_span(4, policy);
+        range_vec.set(2, 3);
+        assert_eq!(
+            range_vec.try_transaction(stage),
+            Err(RangeVecError::SpanOverflow)
+        );
+        assert_eq!(range_vec.range(), Some(2..=2));
+        assert_eq!(range_vec[0], 0);
+
+        // Resets within the transaction make room for its writes
+        let mut range_vec = RangeVec::<u8>::with_max_span(4, policy);
+        range_vec.copy_from_slice(10, &[1, 2, 3, 4]);
+        range_vec
+            .transaction(|tx| {
+                tx.reset(10);
+                tx.set(14, 5);
+                Ok::<(), ()>(())
+            })
+            .unwrap();
+        assert_eq!(range_vec.range(), Some(11..=14));
+    }
+
+    // Only EvictFar makes room by evicting stored values, never the staged ones
+    let stage = |tx: &mut Transaction<'_, u8>| {
+        tx.set(13, 4);
+        tx.set(14, 5);
+        Ok(())
+    };
+    for policy in [OverflowPolicy::Reject, OverflowPolicy::Discard] {
+        let mut range_vec = RangeVec::<u8>::with_max_span(4, policy);
+        range_vec.copy_from_slice(10, &[1, 2, 3]);
+        assert_eq!(
+            range_vec.try_transaction(stage),
+            Err(RangeVecError::SpanOverflow)
+        );
+        assert_eq!(range_vec.range(), Some(10..=12));
+    }
+    let mut range_vec = RangeVec::<u8>::with_max_span(4, OverflowPolicy::EvictFar);
+    range_vec.copy_from_slice(10, &[1, 2, 3]);
+    range_vec.try_transaction(stage).unwrap();
+    assert_eq!(range_vec.range(), Some(11..=14));
+    assert_eq!(
+        range_vec.iter(11..=14).copied().collect::<Vec<_>>(),
+        vec![2, 3, 4, 5]
+    );
+    range_vec
+        .try_transaction(|tx| {
+            tx.set(8, 6);
+            tx.set(9, 7);
+            tx.reset(14);
+            Ok::<(), RangeVecError>(())
+        })
+        .unwrap();
+    assert_eq!(range_vec.range(), Some(8..=11));
+    assert_eq!(
+        range_vec.iter(8..=11).copied().collect::<Vec<_>>(),
+        vec![6, 7, 0, 2]
+    );
+}
+
+#[test]
+#[should_panic(expected = "maximum span exceeded in RangeVec")]
+fn test_transaction_span_overflow() {
+    let mut range_vec = RangeVec::<u8>::with_max_span(4, OverflowPolicy::Reject);
+    let _ = range_vec.transaction(|tx| {
+        tx.set(0, 1);
+        tx.set(10, 2);
+        Ok::<(), ()>(())
+    });
+}